@@ -0,0 +1,94 @@
+// random playouts per second on the board, without a benchmark framework.
+//
+//     cargo bench --bench playouts
+//
+// `legal moves` generates all legal moves before every move, like RandomBot.
+// `first legal` tries empty points in random order until one is legal, like the playouts of MctsBot.
+// `undo` plays as `first legal` on a copy of the board and takes every move back, like the search of MctsBot.
+use gorust::board::{Board, Stone};
+use gorust::bot::random::find_candidate_points;
+use rand::prelude::*;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+// each case runs for about this long
+const DURATION: Duration = Duration::from_secs(2);
+
+fn main() {
+    for size in [9, 19] {
+        run(&format!("{size}x{size} legal moves"), |random| {
+            playout(size, random, |board, stone, random| {
+                let points = find_candidate_points(board, stone);
+                match points.choose(random) {
+                    Some(&point) => board.put(stone, point).is_ok(),
+                    None => false,
+                }
+            })
+            .1
+        });
+        run(&format!("{size}x{size} first legal"), |random| {
+            playout(size, random, put_first_legal).1
+        });
+        run(&format!("{size}x{size} undo"), |random| {
+            let (mut board, moves) = playout(size, random, put_first_legal);
+            // the copy of the played board
+            let mut board = black_box(&mut board).clone();
+            while board.undo().is_ok() {}
+            moves
+        });
+    }
+}
+
+fn put_first_legal(board: &mut Board, stone: Stone, random: &mut StdRng) -> bool {
+    let mut points = board
+        .points()
+        .filter(|&p| board.get(p).is_empty())
+        .collect::<Vec<_>>();
+    points.shuffle(random);
+    points
+        .into_iter()
+        .any(|p| !board.is_eye(stone, p) && board.put(stone, p).is_ok())
+}
+
+// the playouts of a case share one random generator, so each plays a different game.
+// the seed is fixed to compare the runs
+fn run(name: &str, mut playout: impl FnMut(&mut StdRng) -> usize) {
+    let mut random = StdRng::seed_from_u64(0);
+    let start = Instant::now();
+    let mut playouts = 0;
+    let mut moves = 0;
+    while start.elapsed() < DURATION {
+        moves += black_box(playout(&mut random));
+        playouts += 1;
+    }
+    let seconds = start.elapsed().as_secs_f64();
+    println!(
+        "{:<24} {:>10.1} playouts/s {:>12.0} moves/s",
+        name,
+        playouts as f64 / seconds,
+        moves as f64 / seconds
+    );
+}
+
+// a random game until both players pass. returns the board and the number of moves
+fn playout(
+    size: usize,
+    random: &mut StdRng,
+    mut put: impl FnMut(&mut Board, Stone, &mut StdRng) -> bool,
+) -> (Board, usize) {
+    let mut board = Board::new(size);
+    let mut turn = Stone::Black;
+    let mut pass_count = 0;
+    let mut moves = 0;
+    while pass_count < 2 && moves < size * size * 3 {
+        if put(&mut board, turn, random) {
+            pass_count = 0;
+        } else {
+            board.pass(turn);
+            pass_count += 1;
+        }
+        turn = turn.flip();
+        moves += 1;
+    }
+    (board, moves)
+}
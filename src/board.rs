@@ -1,15 +1,23 @@
+pub mod bitboard;
+pub mod zobrist;
+
+use bitboard::{Bitboard, Geometry};
 use regex::Regex;
-use std::{collections::HashSet, fmt};
+use std::fmt;
+use zobrist::HashMapByHash;
 
-pub const BOARD_SIZE: usize = 9;
+pub const DEFAULT_BOARD_SIZE: usize = 9;
+pub const MIN_BOARD_SIZE: usize = 2;
+pub const MAX_BOARD_SIZE: usize = 25;
 const BLACK: &str = "○";
 const WHITE: &str = "●";
-const NUMBERS: [char; 19] = [
+const NUMBERS: [char; MAX_BOARD_SIZE] = [
     '①', '②', '③', '④', '⑤', '⑥', '⑦', '⑧', '⑨', '⑩', //
-    '⑪', '⑫', '⑬', '⑭', '⑮', '⑯', '⑰', '⑱', '⑲',
+    '⑪', '⑫', '⑬', '⑭', '⑮', '⑯', '⑰', '⑱', '⑲', '⑳', //
+    '㉑', '㉒', '㉓', '㉔', '㉕',
 ];
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Stone {
     Black,
     White,
@@ -48,232 +56,623 @@ impl BoardCell {
     }
 }
 
+// which repetitions of the board are forbidden
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum KoRule {
+    // cannot retake a ko right after the opponent took it
+    #[default]
+    Simple,
+    // cannot repeat any previous board position
+    PositionalSuperko,
+    // cannot repeat a previous board position with the same player to move
+    SituationalSuperko,
+}
+
 #[derive(Debug, Clone)]
 pub struct Board {
-    space: Space,
+    size: usize,
+    geometry: Geometry,
+    black: Bitboard,
+    white: Bitboard,
+    // the chains on the board, updated on every move and restored by undo,
+    // so liberties are known without finding the groups
+    chains: Vec<Chain>,
+    // ids in `chains` of the removed chains, to be reused
+    free_chains: Vec<u16>,
+    // the id of the chain of each stone. meaningless on empty points
+    chain_ids: Vec<u16>,
+    // chains as they were before the moves, for undo. the changes tell how many each move saved
+    saved_chains: Vec<Chain>,
     pub black_prisoners: usize,
     pub white_prisoners: usize,
-    previous_spaces: Vec<Space>,
+    pub ko_rule: KoRule,
+    // a suicide move removes its own group instead of being rejected
+    pub suicide_allowed: bool,
+    // zobrist hash of the stones, updated on every change of the space
+    position_hash: u64,
+    side_to_move: Stone,
+    // position hashes after every move, the first one is the initial position
+    previous_positions: Vec<u64>,
+    // for superko. how many times positions, and positions with the side to move, appeared
+    seen_positions: HashMapByHash<usize>,
+    seen_situations: HashMapByHash<usize>,
+    // every move and pass since the initial position, to take them back
+    changes: Vec<Change>,
 }
 
-type Space = [[Option<Stone>; BOARD_SIZE]; BOARD_SIZE];
+// what a move or a pass changed on the board
+#[derive(Debug, Clone)]
+struct Change {
+    stone: Stone,
+    // None for pass
+    point: Option<Point>,
+    captured: Bitboard,
+    // own stones removed by suicide, including the point
+    self_captured: Bitboard,
+    // own chains joined by the move and captured chains, saved on `saved_chains`
+    saved_chains: u8,
+    side_to_move: Stone,
+}
+
+// connected stones of the same color and the empty points next to them
+#[derive(Debug, Clone, Copy)]
+struct Chain {
+    stones: Bitboard,
+    liberties: Bitboard,
+}
+
+// stones removed by a move
+#[derive(Debug, Clone, Copy)]
+struct Removed {
+    captured: Bitboard,
+    // own group without breathing space, only when nothing is captured
+    suicide: Bitboard,
+    // ids of the own chains joined by the move and of the captured chains
+    chains: Bitboard,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoardError {
+    OutOfRange(Point),
+    Occupied(Point),
+    Suicide(Point),
+    // the ko cannot be taken back at the point at once
+    Ko(Point),
+    // the move repeats a previous position
+    Superko(Point),
+    NoMoveToUndo,
+    Handicap { handicap: usize, size: usize },
+}
+
+impl fmt::Display for BoardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardError::OutOfRange(point) => {
+                write!(f, "the point: {:?} is out of board range", point)
+            }
+            BoardError::Occupied(point) => {
+                write!(f, "a stone is already on the point: {:?}", point)
+            }
+            BoardError::Suicide(point) => write!(f, "{:?} is a suicide move", point),
+            BoardError::Ko(point) => write!(f, "cannot take KO at {:?}", point),
+            BoardError::Superko(point) => {
+                write!(f, "{:?} repeats a previous position (superko)", point)
+            }
+            BoardError::NoMoveToUndo => write!(f, "no move to undo"),
+            BoardError::Handicap { handicap, size } => write!(
+                f,
+                "handicap {} is not available on {}x{} board",
+                handicap, size, size
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BoardError {}
 
 impl Board {
-    pub fn new() -> Self {
-        let space = [[None; BOARD_SIZE]; BOARD_SIZE];
-        Self {
-            space,
+    // panics unless MIN_BOARD_SIZE <= size <= MAX_BOARD_SIZE
+    pub fn new(size: usize) -> Self {
+        assert!(
+            Self::is_valid_size(size),
+            "board size must be between {} and {}, got {}",
+            MIN_BOARD_SIZE,
+            MAX_BOARD_SIZE,
+            size
+        );
+        let mut board = Self {
+            size,
+            geometry: Geometry::new(size),
+            black: Bitboard::EMPTY,
+            white: Bitboard::EMPTY,
+            chains: vec![],
+            free_chains: vec![],
+            chain_ids: vec![0; size * size],
+            saved_chains: vec![],
             black_prisoners: 0,
             white_prisoners: 0,
-            previous_spaces: vec![space],
-        }
+            ko_rule: KoRule::default(),
+            suicide_allowed: false,
+            position_hash: 0,
+            side_to_move: Stone::Black,
+            previous_positions: vec![],
+            seen_positions: HashMapByHash::default(),
+            seen_situations: HashMapByHash::default(),
+            changes: vec![],
+        };
+        board.reset_history();
+        board
     }
 
-    pub fn new_with_prisoners(black_prisoners: usize, white_prisoners: usize) -> Self {
-        let mut board = Self::new();
+    pub fn new_with_prisoners(size: usize, black_prisoners: usize, white_prisoners: usize) -> Self {
+        let mut board = Self::new(size);
         board.black_prisoners += black_prisoners;
         board.white_prisoners += white_prisoners;
         board
     }
 
+    pub fn is_valid_size(size: usize) -> bool {
+        (MIN_BOARD_SIZE..=MAX_BOARD_SIZE).contains(&size)
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    // all points on the board, row by row
+    pub fn points(&self) -> impl Iterator<Item = Point> + use<> {
+        let size = self.size as i8;
+        (1..=size).flat_map(move |row| (1..=size).map(move |col| Point { row, col }))
+    }
+
     pub fn get(&self, point: Point) -> BoardCell {
         // check point range
         if point.row <= 0
-            || BOARD_SIZE < point.row as usize
+            || self.size < point.row as usize
             || point.col <= 0
-            || BOARD_SIZE < point.col as usize
+            || self.size < point.col as usize
         {
             BoardCell::Wall
         } else {
-            BoardCell::Space(self.space[point.row as usize - 1][point.col as usize - 1])
+            BoardCell::Space(self.stone_at(self.index(point)))
         }
     }
 
-    pub fn put(&mut self, stone: Stone, point: Point) -> Result<(), String> {
-        // validate for go rule
-        match self.can_put(stone, point) {
-            Ok(_) => {
-                self.kill_by(stone, point);
-                self.space[point.row as usize - 1][point.col as usize - 1] = Some(stone);
-                self.previous_spaces.push(self.space.clone());
-                Ok(())
+    fn stone_at(&self, index: usize) -> Option<Stone> {
+        if self.black.contains(index) {
+            Some(Stone::Black)
+        } else if self.white.contains(index) {
+            Some(Stone::White)
+        } else {
+            None
+        }
+    }
+
+    fn stones(&self, stone: Stone) -> Bitboard {
+        match stone {
+            Stone::Black => self.black,
+            Stone::White => self.white,
+        }
+    }
+
+    fn empty_points(&self) -> Bitboard {
+        self.geometry.all() & !(self.black | self.white)
+    }
+
+    fn point_at(&self, index: usize) -> Point {
+        Point {
+            row: (index / self.size) as i8 + 1,
+            col: (index % self.size) as i8 + 1,
+        }
+    }
+
+    // the caller must make sure that the point is on the board
+    fn index(&self, point: Point) -> usize {
+        (point.row as usize - 1) * self.size + (point.col as usize - 1)
+    }
+
+    // put the stone without go rules, joining the chains next to it.
+    // the caller removes the captured stones first
+    fn add_stone(&mut self, stone: Stone, index: usize) {
+        self.position_hash ^= zobrist::key(index, stone);
+        let put = Bitboard::single(index);
+        let neighbors = self.geometry.neighbors(put);
+        let mut chain = Chain {
+            stones: put,
+            liberties: neighbors & self.empty_points(),
+        };
+        for id in self.chain_ids_of(neighbors & self.stones(stone)).iter() {
+            let joined = self.chains[id];
+            chain.stones |= joined.stones;
+            chain.liberties |= joined.liberties;
+            self.free_chains.push(id as u16);
+        }
+        chain.liberties.remove(index);
+        // the opponent's chains next to the stone lose the breathing space
+        for id in self
+            .chain_ids_of(neighbors & self.stones(stone.flip()))
+            .iter()
+        {
+            self.chains[id].liberties.remove(index);
+        }
+        match stone {
+            Stone::Black => self.black.insert(index),
+            Stone::White => self.white.insert(index),
+        }
+        self.insert_chain(chain);
+    }
+
+    // change the cell without updating the chains
+    fn set_index(&mut self, index: usize, cell: Option<Stone>) {
+        if let Some(stone) = self.stone_at(index) {
+            self.position_hash ^= zobrist::key(index, stone);
+        }
+        self.black.remove(index);
+        self.white.remove(index);
+        if let Some(stone) = cell {
+            self.position_hash ^= zobrist::key(index, stone);
+            match stone {
+                Stone::Black => self.black.insert(index),
+                Stone::White => self.white.insert(index),
             }
-            Err(err) => Err(format!("cannot put stone: {}", err)),
         }
     }
 
-    pub fn find_available_points(&mut self, stone: Stone) -> Vec<Point> {
-        let mut available_points = vec![];
-        for row in 1..=BOARD_SIZE {
-            for col in 1..=BOARD_SIZE {
-                let point = Point {
-                    row: row as i8,
-                    col: col as i8,
-                };
-                if self.can_put(stone, point).is_ok() {
-                    available_points.push(point);
+    // remove the stones of the set, which are whole chains of the color
+    fn remove_stones(&mut self, stone: Stone, set: Bitboard) {
+        // most moves remove nothing
+        if set.is_empty() {
+            return;
+        }
+        for index in set.iter() {
+            self.position_hash ^= zobrist::key(index, stone);
+        }
+        for id in self.chain_ids_of(set).iter() {
+            self.free_chains.push(id as u16);
+        }
+        match stone {
+            Stone::Black => self.black &= !set,
+            Stone::White => self.white &= !set,
+        }
+        // the chains next to the removed stones breathe there
+        let geometry = self.geometry;
+        for id in self
+            .chain_ids_of(geometry.neighbors(set) & (self.black | self.white))
+            .iter()
+        {
+            let chain = &mut self.chains[id];
+            chain.liberties |= geometry.neighbors(chain.stones) & set;
+        }
+    }
+
+    // store the chain under a free id
+    fn insert_chain(&mut self, chain: Chain) {
+        let id = match self.free_chains.pop() {
+            Some(id) => id as usize,
+            None => {
+                self.chains.push(chain);
+                self.chains.len() - 1
+            }
+        };
+        self.chains[id] = chain;
+        for index in chain.stones.iter() {
+            self.chain_ids[index] = id as u16;
+        }
+    }
+
+    // ids of the chains with stones in the set. ids are below the number of points,
+    // so they fit in a bitboard
+    fn chain_ids_of(&self, set: Bitboard) -> Bitboard {
+        let mut ids = Bitboard::EMPTY;
+        for index in set.iter() {
+            ids.insert(self.chain_ids[index] as usize);
+        }
+        ids
+    }
+
+    fn chain_at(&self, point: Point) -> Option<&Chain> {
+        if self.get(point).is_stone() {
+            Some(&self.chains[self.chain_ids[self.index(point)] as usize])
+        } else {
+            None
+        }
+    }
+
+    fn points_of(&self, set: Bitboard) -> Vec<Point> {
+        set.iter().map(|index| self.point_at(index)).collect()
+    }
+
+    // stones of the group at the point, row by row. empty without a stone
+    pub fn group_at(&self, point: Point) -> Vec<Point> {
+        self.chain_at(point)
+            .map_or(vec![], |chain| self.points_of(chain.stones))
+    }
+
+    // breathing spaces of the group at the point, row by row. empty without a stone
+    pub fn liberties(&self, point: Point) -> Vec<Point> {
+        self.chain_at(point)
+            .map_or(vec![], |chain| self.points_of(chain.liberties))
+    }
+
+    // the number of the liberties without collecting them, e.g. 1 for atari
+    pub fn liberty_count(&self, point: Point) -> usize {
+        self.chain_at(point)
+            .map_or(0, |chain| chain.liberties.len())
+    }
+
+    // zobrist hash of the stones and the side to move
+    pub fn hash(&self) -> u64 {
+        situation_hash(self.position_hash, self.side_to_move)
+    }
+
+    pub fn side_to_move(&self) -> Stone {
+        self.side_to_move
+    }
+
+    pub fn pass(&mut self, stone: Stone) {
+        self.changes.push(Change {
+            stone,
+            point: None,
+            captured: Bitboard::EMPTY,
+            self_captured: Bitboard::EMPTY,
+            saved_chains: 0,
+            side_to_move: self.side_to_move,
+        });
+        self.side_to_move = stone.flip();
+    }
+
+    pub fn put(&mut self, stone: Stone, point: Point) -> Result<(), BoardError> {
+        // validate for go rule
+        let removed = self.check_put(stone, point)?;
+        let index = self.index(point);
+        // the chains which undo brings back
+        for id in removed.chains.iter() {
+            self.saved_chains.push(self.chains[id]);
+        }
+        self.remove_stones(stone.flip(), removed.captured);
+        self.add_stone(stone, index);
+        // only reached when suicide is allowed
+        self.remove_stones(stone, removed.suicide);
+        // own stones are prisoners of the opponent
+        let (own_prisoners, opponent_prisoners) = match stone {
+            Stone::Black => (&mut self.black_prisoners, &mut self.white_prisoners),
+            Stone::White => (&mut self.white_prisoners, &mut self.black_prisoners),
+        };
+        *own_prisoners += removed.captured.len();
+        *opponent_prisoners += removed.suicide.len();
+        self.changes.push(Change {
+            stone,
+            point: Some(point),
+            captured: removed.captured,
+            self_captured: removed.suicide,
+            saved_chains: removed.chains.len() as u8,
+            side_to_move: self.side_to_move,
+        });
+        self.side_to_move = stone.flip();
+        self.record_position();
+        Ok(())
+    }
+
+    // take back the last move or pass. setup stones cannot be taken back.
+    pub fn undo(&mut self) -> Result<(), BoardError> {
+        let change = self.changes.pop().ok_or(BoardError::NoMoveToUndo)?;
+        if let Some(point) = change.point {
+            // forget the position after the move
+            let situation = self.hash();
+            self.previous_positions.pop();
+            forget(&mut self.seen_positions, self.position_hash);
+            forget(&mut self.seen_situations, situation);
+
+            let index = self.index(point);
+            // the chain of the stone is gone already after a suicide
+            if change.self_captured.is_empty() {
+                self.free_chains.push(self.chain_ids[index]);
+            }
+            for index in change.self_captured.iter() {
+                self.set_index(index, Some(change.stone));
+            }
+            self.set_index(index, None);
+            for index in change.captured.iter() {
+                self.set_index(index, Some(change.stone.flip()));
+            }
+            let mut restored = Bitboard::EMPTY;
+            for _ in 0..change.saved_chains {
+                let chain = self.saved_chains.pop().expect("the move saved the chains");
+                restored |= chain.stones;
+                self.insert_chain(chain);
+            }
+            // the opponent's chains next to the point breathe there again
+            let geometry = self.geometry;
+            let others = (self.black | self.white) & !restored;
+            let point_set = Bitboard::single(index);
+            for id in self
+                .chain_ids_of(geometry.neighbors(point_set) & others)
+                .iter()
+            {
+                self.chains[id].liberties.insert(index);
+            }
+            // and lose the breathing spaces where removed stones are put back
+            let put_back = (change.captured | change.self_captured) & !point_set;
+            if !put_back.is_empty() {
+                for id in self
+                    .chain_ids_of(geometry.neighbors(put_back) & others)
+                    .iter()
+                {
+                    self.chains[id].liberties &= !put_back;
                 }
             }
+            let (own_prisoners, opponent_prisoners) = match change.stone {
+                Stone::Black => (&mut self.black_prisoners, &mut self.white_prisoners),
+                Stone::White => (&mut self.white_prisoners, &mut self.black_prisoners),
+            };
+            *own_prisoners -= change.captured.len();
+            *opponent_prisoners -= change.self_captured.len();
         }
-        available_points
+        self.side_to_move = change.side_to_move;
+        Ok(())
     }
 
-    fn can_put(&mut self, stone: Stone, point: Point) -> Result<(), String> {
+    // place a stone without go rules, e.g. for handicap or SGF setup stones.
+    // the resulting position becomes the start of the history.
+    pub fn setup(&mut self, stone: Stone, point: Point) -> Result<(), BoardError> {
+        let board_cell = self.get(point);
+        if board_cell.is_wall() {
+            Err(BoardError::OutOfRange(point))
+        } else if board_cell.is_stone() {
+            Err(BoardError::Occupied(point))
+        } else {
+            self.add_stone(stone, self.index(point));
+            self.reset_history();
+            Ok(())
+        }
+    }
+
+    // start the history from the current position
+    fn reset_history(&mut self) {
+        self.previous_positions.clear();
+        self.seen_positions.clear();
+        self.seen_situations.clear();
+        self.changes.clear();
+        self.saved_chains.clear();
+        self.previous_positions.push(self.position_hash);
+        *self.seen_positions.entry(self.position_hash).or_default() += 1;
+        // the side to move at the initial position is not known, forbid both
+        for stone in [Stone::Black, Stone::White] {
+            *self
+                .seen_situations
+                .entry(situation_hash(self.position_hash, stone))
+                .or_default() += 1;
+        }
+    }
+
+    fn record_position(&mut self) {
+        self.previous_positions.push(self.position_hash);
+        *self.seen_positions.entry(self.position_hash).or_default() += 1;
+        *self.seen_situations.entry(self.hash()).or_default() += 1;
+    }
+
+    pub fn find_available_points(&self, stone: Stone) -> Vec<Point> {
+        self.empty_points()
+            .iter()
+            .map(|index| self.point_at(index))
+            .filter(|&point| self.can_put(stone, point).is_ok())
+            .collect()
+    }
+
+    fn can_put(&self, stone: Stone, point: Point) -> Result<(), BoardError> {
+        self.check_put(stone, point).map(|_| ())
+    }
+
+    // the stones the move would remove, if the move is legal
+    fn check_put(&self, stone: Stone, point: Point) -> Result<Removed, BoardError> {
         let board_cell = self.get(point);
         // validate point range
         if board_cell.is_wall() {
-            Err(format!("the point: {:?} is out of board range", point))
+            return Err(BoardError::OutOfRange(point));
         }
         // #################
         // Go rules
         // #################
         // cannot put a stone on the existing stone.
-        else if board_cell.is_stone() {
-            Err(format!("a stone is already on the point: {:?}", point))
+        if board_cell.is_stone() {
+            return Err(BoardError::Occupied(point));
+        }
+        let removed = self.removed_by(stone, point);
         // cannot put a stone if the stones connected with it will be killed. but can put when can kill.
-        } else if self.is_suicide(stone, point)
-            && self.find_groups_can_kill(stone, point).len() == 0
-        {
-            Err(format!("it's a suicide move."))
+        if !self.suicide_allowed && !removed.suicide.is_empty() {
+            Err(BoardError::Suicide(point))
         }
         // cannot put a stone make same space as the last space.
         // ko is occurred.
-        else if self.is_same_last_space(stone, point) {
-            Err(format!("cannot take KO at {:?} for {:?}", point, stone))
+        else if self.ko_rule == KoRule::Simple && self.is_same_last_space(stone, point, removed) {
+            Err(BoardError::Ko(point))
+        }
+        // with superko, cannot repeat any space in the history.
+        else if self.ko_rule != KoRule::Simple && self.is_repeated_space(stone, point, removed) {
+            Err(BoardError::Superko(point))
         } else {
-            Ok(())
+            Ok(removed)
         }
     }
 
-    fn kill_by(&mut self, stone: Stone, point: Point) {
-        let groups = self.find_groups_can_kill(stone, point);
-        if groups.len() == 0 {
-            return;
-        }
-        // remove all groups
-        for group in groups.iter() {
-            for p in group.iter() {
-                self.space[p.row as usize - 1][p.col as usize - 1] = None;
+    // the opponent's stones and own stones removed by putting the stone on the empty point.
+    // a chain next to the point loses its last breathing space when it has only the point
+    fn removed_by(&self, stone: Stone, point: Point) -> Removed {
+        let mut captured = Bitboard::EMPTY;
+        let mut suicide = Bitboard::single(self.index(point));
+        let mut chains = Bitboard::EMPTY;
+        let mut breathes = false;
+        for p in point.neighbors() {
+            let BoardCell::Space(cell) = self.get(p) else {
+                continue;
+            };
+            let Some(neighbor) = cell else {
+                breathes = true;
+                continue;
+            };
+            let id = self.chain_ids[self.index(p)] as usize;
+            let chain = &self.chains[id];
+            let in_atari = chain.liberties.len() == 1;
+            if neighbor == stone || in_atari {
+                chains.insert(id);
             }
-            // add numbers of group to prisoners
-            match stone {
-                Stone::Black => {
-                    self.black_prisoners += group.len();
-                }
-                Stone::White => {
-                    self.white_prisoners += group.len();
-                }
+            if neighbor != stone && in_atari {
+                captured |= chain.stones;
+            } else if neighbor == stone && in_atari {
+                suicide |= chain.stones;
+            } else if neighbor == stone {
+                breathes = true;
             }
         }
+        if breathes || !captured.is_empty() {
+            suicide = Bitboard::EMPTY;
+        }
+        Removed {
+            captured,
+            suicide,
+            chains,
+        }
     }
 
-    fn find_groups_can_kill(&self, stone: Stone, point: Point) -> Vec<Vec<Point>> {
-        // todo: refacter not to use unwrap
-        [
-            // find opponent's stone from around
-            point.up(),
-            point.down(),
-            point.left(),
-            point.right(),
-        ]
-        .into_iter()
-        // choose opponent's stones
-        .filter(|&p| self.get(p).is_same_color(stone.flip()))
-        // find groups of opponent's stones
-        .map(|p| self.find_group(stone.flip(), p))
-        // choose group that breathing space is 1 and given point
-        .filter_map(|g| {
-            let breathing_space = self.find_breathing_space(g.clone());
-            if breathing_space.len() == 1 && breathing_space[0] == point {
-                Some(g)
-            } else {
-                None
-            }
-        })
-        .collect()
-    }
-
-    fn find_breathing_space(&self, group: Vec<Point>) -> Vec<Point> {
-        // todo: refoctor not to use unwrap
-        if group.len() == 0 {
-            return vec![];
-        }
-        let mut breathing_points = vec![];
-        let mut checked_points = HashSet::new();
-        let mut check_points = vec![];
-        for p in group.into_iter() {
-            // group points are all opponent's color stones, so no need to check.
-            checked_points.insert(p);
-            check_points.append(&mut vec![p.up(), p.down(), p.right(), p.left()]);
-        }
-        // check around the point
-        while let Some(check_point) = check_points.pop() {
-            // is check_point already checked?
-            if checked_points.contains(&check_point) {
-                continue;
-            }
-            // if the cell is empty
-            if self.get(check_point).is_empty() {
-                breathing_points.push(check_point);
-                checked_points.insert(check_point);
-            }
+    fn is_same_last_space(&self, stone: Stone, point: Point, removed: Removed) -> bool {
+        let history_length = self.previous_positions.len();
+        if history_length < 4 {
+            return false;
         }
-        breathing_points
+        self.position_hash_after(stone, point, removed)
+            == self.previous_positions[history_length - 2]
     }
 
-    fn find_group(&self, stone: Stone, start_point: Point) -> Vec<Point> {
-        let mut group = vec![];
-        let mut checked_points = HashSet::new();
-        let mut check_points = vec![start_point];
-
-        while let Some(point) = check_points.pop() {
-            // check if the node is known
-            if checked_points.contains(&point) {
-                continue;
-            }
-            // check if the node's color is same
-            if self.get(point).is_same_color(stone) {
-                // add same color to group
-                group.push(point);
-                // add up,down,left and right of the node to stack to check around the node
-                check_points.append(&mut vec![
-                    point.up(),
-                    point.down(),
-                    point.left(),
-                    point.right(),
-                ]);
-            }
-            checked_points.insert(point);
+    fn is_repeated_space(&self, stone: Stone, point: Point, removed: Removed) -> bool {
+        let position_hash = self.position_hash_after(stone, point, removed);
+        match self.ko_rule {
+            KoRule::SituationalSuperko => self
+                .seen_situations
+                .contains_key(&situation_hash(position_hash, stone.flip())),
+            _ => self.seen_positions.contains_key(&position_hash),
         }
-
-        group
     }
 
-    fn is_suicide(&mut self, stone: Stone, point: Point) -> bool {
-        // todo: refactor? implementing temporary put method is very considerable.
-        // put stone temporary
-        self.space[point.row as usize - 1][point.col as usize - 1] = Some(stone);
-        // calculate breathing space of put stone
-        let group = self.find_group(stone, point);
-        let breathing_space = self.find_breathing_space(group);
-        // remove stone put temporary
-        self.space[point.row as usize - 1][point.col as usize - 1] = None;
-        breathing_space.len() == 0
+    // the position hash if the stone was put, without changing the board
+    fn position_hash_after(&self, stone: Stone, point: Point, removed: Removed) -> u64 {
+        let mut hash = self.position_hash ^ zobrist::key(self.index(point), stone);
+        for index in removed.captured.iter() {
+            hash ^= zobrist::key(index, stone.flip());
+        }
+        // the put stone itself is removed too
+        for index in removed.suicide.iter() {
+            hash ^= zobrist::key(index, stone);
+        }
+        hash
     }
 
-    fn is_same_last_space(&mut self, stone: Stone, point: Point) -> bool {
-        let history_length = self.previous_spaces.len();
-        if history_length < 4 {
-            return false;
-        }
-        // todo: refactor? implementing temporary put method is very considerable.
-        // put stone temporary
-        let mut temp_board = Self::new();
-        temp_board.space = self.space;
-        temp_board.kill_by(stone, point);
-        temp_board.space[point.row as usize - 1][point.col as usize - 1] = Some(stone);
-        // check
-        temp_board.space == self.previous_spaces[history_length - 2]
+    // the point where the side to move cannot retake the ko right now
+    pub fn ko_point(&self) -> Option<Point> {
+        let stone = self.side_to_move;
+        self.empty_points()
+            .iter()
+            .map(|index| self.point_at(index))
+            .find(|&p| self.is_same_last_space(stone, p, self.removed_by(stone, p)))
     }
 
     pub fn is_eye(&self, stone: Stone, point: Point) -> bool {
@@ -292,18 +691,108 @@ impl Board {
         .count()
             > 6
     }
+
+    // fixed handicap stones in the order of GTP fixed_handicap.
+    // 2 to 9 stones on odd boards from 9x9, 2 to 4 stones on 7x7 and even boards.
+    pub fn handicap_points(&self, handicap: usize) -> Result<Vec<Point>, BoardError> {
+        let max_handicap = match self.size {
+            ..7 => 0,
+            7 => 4,
+            size if size % 2 == 0 => 4,
+            _ => 9,
+        };
+        if !(2..=max_handicap).contains(&handicap) {
+            return Err(BoardError::Handicap {
+                handicap,
+                size: self.size,
+            });
+        }
+        let size = self.size as i8;
+        let edge = if self.size >= 13 { 4 } else { 3 };
+        let far = size + 1 - edge;
+        let center = size / 2 + 1;
+        // lower left, upper right, upper left, lower right
+        let corners = [(far, edge), (edge, far), (edge, edge), (far, far)];
+        // left, right, lower, upper
+        let sides = [(center, edge), (center, far), (far, center), (edge, center)];
+        let tengen = (center, center);
+        let mut points = corners[..handicap.min(4)].to_vec();
+        match handicap {
+            5 => points.push(tengen),
+            6 => points.extend_from_slice(&sides[..2]),
+            7 => {
+                points.extend_from_slice(&sides[..2]);
+                points.push(tengen);
+            }
+            8 => points.extend_from_slice(&sides),
+            9 => {
+                points.extend_from_slice(&sides);
+                points.push(tengen);
+            }
+            _ => {}
+        }
+        Ok(points
+            .into_iter()
+            .map(|(row, col)| Point { row, col })
+            .collect())
+    }
+
+    // hoshi positions, following the usual 9x9, 13x13 and 19x19 layouts
+    pub fn star_points(&self) -> Vec<Point> {
+        let size = self.size as i8;
+        // distance of the corner hoshi from the edge
+        let edge = match self.size {
+            13.. => 4,
+            7.. => 3,
+            _ => return vec![],
+        };
+        let far = size + 1 - edge;
+        let center = size / 2 + 1;
+        let mut lines = vec![(edge, edge), (edge, far), (far, edge), (far, far)];
+        if self.size % 2 == 1 {
+            // tengen
+            lines.push((center, center));
+            // hoshi at hen
+            if self.size >= 13 {
+                lines.append(&mut vec![
+                    (edge, center),
+                    (center, edge),
+                    (far, center),
+                    (center, far),
+                ]);
+            }
+        }
+        lines
+            .into_iter()
+            .map(|(row, col)| Point { row, col })
+            .collect()
+    }
+}
+
+fn situation_hash(position_hash: u64, side_to_move: Stone) -> u64 {
+    match side_to_move {
+        Stone::Black => position_hash,
+        Stone::White => position_hash ^ zobrist::WHITE_TO_MOVE,
+    }
+}
+
+// decrease the count of the hash, removing it at zero
+fn forget(seen: &mut HashMapByHash<usize>, hash: u64) {
+    if let Some(count) = seen.get_mut(&hash) {
+        *count -= 1;
+        if *count == 0 {
+            seen.remove(&hash);
+        }
+    }
 }
 
 impl PartialEq for Board {
     fn eq(&self, other: &Self) -> bool {
-        self.space == other.space
+        self.black == other.black
+            && self.white == other.white
             && self.black_prisoners == other.black_prisoners
             && self.white_prisoners == other.white_prisoners
     }
-
-    fn ne(&self, other: &Self) -> bool {
-        !self.eq(other)
-    }
 }
 
 // One origin to express domain
@@ -342,6 +831,10 @@ impl Point {
             col: self.col + 1,
         }
     }
+
+    pub fn neighbors(&self) -> [Point; 4] {
+        [self.up(), self.down(), self.left(), self.right()]
+    }
 }
 
 impl fmt::Display for Stone {
@@ -357,116 +850,89 @@ impl TryFrom<String> for Point {
     type Error = String;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        let re = Regex::new(r"([0-9]+),([0-9]+)").expect("failed to parse the String to Point");
-        for (_, [row, col]) in re.captures_iter(&value).map(|c| c.extract()) {
-            return Ok(Self {
-                row: row.parse::<i8>().expect("failed to parse number"),
-                col: col.parse::<i8>().expect("failed to parse number"),
-            });
+        let re = Regex::new(r"^\s*([0-9]+)\s*,\s*([0-9]+)\s*$")
+            .expect("failed to parse the String to Point");
+        match re.captures(&value).map(|c| c.extract()) {
+            Some((_, [row, col])) => match (row.parse::<i8>(), col.parse::<i8>()) {
+                (Ok(row), Ok(col)) => Ok(Self { row, col }),
+                _ => Err(format!("the number is too large: {}", value.trim())),
+            },
+            None => Err("failed to parse, Point pattern not found in the String".to_string()),
+        }
+    }
+}
+
+impl Board {
+    // two characters of the point drawn by Display: the stone or the grid line to the right
+    pub fn cell_text(&self, point: Point, star_points: &[Point]) -> String {
+        let size = self.size as i8;
+        let (i, j) = (point.row, point.col);
+        if let BoardCell::Space(Some(stone)) = self.get(point) {
+            format!("{} ", stone)
+        } else if i == 1 && j == 1 {
+            "┌─".to_string()
+        // draw hoshi
+        } else if star_points.contains(&point) {
+            "•─".to_string()
+        } else if i == 1 && j == size {
+            "┐ ".to_string()
+        } else if i == size && j == 1 {
+            "└─".to_string()
+        } else if i == size && j == size {
+            "┘ ".to_string()
+        } else if i == 1 {
+            "┬─".to_string()
+        } else if i == size {
+            "┴─".to_string()
+        } else if j == 1 {
+            "├─".to_string()
+        } else if j == size {
+            "┤ ".to_string()
+        } else {
+            "┼─".to_string()
         }
-        Err("failed to parse, Point pattern not found in the String".to_string())
     }
 }
 
 impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let size = self.size;
+        let star_points = self.star_points();
         // upper line
         write!(f, "┌")?;
-        write!(
-            f,
-            "{}",
-            (0..BOARD_SIZE * 2 + 5)
-                .into_iter()
-                .map(|_| "─")
-                .collect::<String>()
-        )?;
-        write!(f, "┐\n")?;
+        write!(f, "{}", "─".repeat(size * 2 + 5))?;
+        writeln!(f, "┐")?;
 
         // upper coordination
         write!(f, "│  ")?;
-        for i in 0..BOARD_SIZE {
-            write!(f, " {}", NUMBERS[i])?;
+        for number in NUMBERS.iter().take(size) {
+            write!(f, " {}", number)?;
         }
-        write!(f, "   │\n")?;
+        writeln!(f, "   │")?;
 
-        for i in 0..BOARD_SIZE {
+        for (i, number) in NUMBERS.iter().enumerate().take(size) {
             // left side line
-            write!(f, "│ {} ", NUMBERS[i])?;
-            for j in 0..BOARD_SIZE {
-                if let Some(stone) = self.space[i][j] {
-                    write!(f, "{} ", stone)?;
-                } else {
-                    if i == 0 && j == 0 {
-                        write!(f, "{}", "┌─")?;
-                    // draw hoshi
-                    } else if BOARD_SIZE == 9
-                        && (
-                            // draw hoshi at sumi
-                            (i == 2 && j == 2)
-                            || (i == 2 && j == BOARD_SIZE - 3)
-                            || (i == BOARD_SIZE - 3 && j == 2)
-                            || (i == BOARD_SIZE - 3 && j == BOARD_SIZE - 3)
-                            // draw tengen
-                            || (i == BOARD_SIZE / 2 && j == BOARD_SIZE / 2)
-                        )
-                    {
-                        write!(f, "•─")?;
-                    } else if (BOARD_SIZE == 13 || BOARD_SIZE == 19)
-                        && (
-                            // draw hoshi at sumi
-                            (i == 3 && j == 3)
-                            || (i == 3 && j == BOARD_SIZE - 4)
-                            || (i == BOARD_SIZE - 4 && j == 3)
-                            || (i == BOARD_SIZE - 4 && j == BOARD_SIZE - 4)
-                            // draw tengen
-                            || (i == BOARD_SIZE / 2 && j == BOARD_SIZE / 2)
-                            // drow hoshi at hen
-                            || (i == 3 && j == BOARD_SIZE / 2)
-                            || (i == BOARD_SIZE / 2 && j == 3)
-                            || (i == BOARD_SIZE - 4 && j == BOARD_SIZE / 2)
-                            || (i == BOARD_SIZE / 2 && j == BOARD_SIZE - 4)
-                        )
-                    {
-                        write!(f, "•─")?;
-                    } else if i == 0 && j == BOARD_SIZE - 1 {
-                        write!(f, "{}", "┐ ")?;
-                    } else if i == BOARD_SIZE - 1 && j == 0 {
-                        write!(f, "└─")?;
-                    } else if i == BOARD_SIZE - 1 && j == BOARD_SIZE - 1 {
-                        write!(f, "┘ ")?;
-                    } else if i == 0 && j != 0 {
-                        write!(f, "{}", "┬─")?;
-                    } else if i == BOARD_SIZE - 1 && j != 0 {
-                        write!(f, "{}", "┴─")?;
-                    } else if i != 0 && j == 0 {
-                        write!(f, "{}", "├─")?;
-                    } else if i != 0 && j == BOARD_SIZE - 1 {
-                        write!(f, "{}", "┤ ")?;
-                    } else {
-                        write!(f, "{}", "┼─")?;
-                    }
-                }
+            write!(f, "│ {} ", number)?;
+            for j in 0..size {
+                let point = Point {
+                    row: i as i8 + 1,
+                    col: j as i8 + 1,
+                };
+                write!(f, "{}", self.cell_text(point, &star_points))?;
             }
             // right side line
-            write!(f, "{} │\n", NUMBERS[i])?;
+            writeln!(f, "{} │", number)?;
         }
         // lower coordination
         write!(f, "│  ")?;
-        for i in 0..BOARD_SIZE {
-            write!(f, " {}", NUMBERS[i])?;
+        for number in NUMBERS.iter().take(size) {
+            write!(f, " {}", number)?;
         }
-        write!(f, "   │\n")?;
+        writeln!(f, "   │")?;
         // lower line
         write!(f, "└")?;
-        write!(
-            f,
-            "{}",
-            (0..BOARD_SIZE * 2 + 5)
-                .into_iter()
-                .map(|_| "─")
-                .collect::<String>()
-        )?;
-        write!(f, "┘\n")?;
+        write!(f, "{}", "─".repeat(size * 2 + 5))?;
+        writeln!(f, "┘")?;
         Ok(())
     }
 }
@@ -474,6 +940,7 @@ impl fmt::Display for Board {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashSet;
 
     #[test]
     fn point_try_from() {
@@ -493,33 +960,44 @@ mod tests {
         let result = Point::try_from(given).unwrap();
         assert_eq!(result, Point { row: 10, col: 10 });
 
+        let given = " 3 , 4 ".to_string();
+        let result = Point::try_from(given).unwrap();
+        assert_eq!(result, Point { row: 3, col: 4 });
+
         // failure case
         let given = "abc".to_string();
         let result = Point::try_from(given);
         assert!(result.is_err());
+
+        for given in ["300,1", "1,2,3", "-1,2", "1,"] {
+            assert!(Point::try_from(given.to_string()).is_err());
+        }
     }
 
     #[test]
     fn board_put() {
-        let mut board = Board::new();
+        let mut board = Board::new(9);
 
         let _ = board.put(Stone::Black, Point { row: 1, col: 1 });
         let _ = board.put(Stone::White, Point { row: 1, col: 2 });
 
-        let mut expected = [[None; BOARD_SIZE]; BOARD_SIZE];
-        expected[0][0] = Some(Stone::Black);
-        expected[0][1] = Some(Stone::White);
-        for i in 0..BOARD_SIZE {
-            for j in 0..BOARD_SIZE {
-                assert_eq!(board.space[i][j], expected[i][j]);
-            }
-        }
+        let mut expected = vec![None; 9 * 9];
+        expected[0] = Some(Stone::Black);
+        expected[1] = Some(Stone::White);
+        let space = board
+            .points()
+            .map(|p| match board.get(p) {
+                BoardCell::Space(cell) => cell,
+                BoardCell::Wall => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(space, expected);
     }
 
     #[test]
     fn board_can_put() {
         // ok
-        let mut board = Board::new();
+        let board = Board::new(9);
         assert!(
             board
                 .can_put(Stone::Black, Point { row: 1, col: 1 })
@@ -532,11 +1010,10 @@ mod tests {
         );
 
         // ng
-        let mut board = Board::new();
-        assert!(
-            board
-                .can_put(Stone::Black, Point { row: 0, col: 1 })
-                .is_err()
+        let mut board = Board::new(9);
+        assert_eq!(
+            board.can_put(Stone::Black, Point { row: 0, col: 1 }),
+            Err(BoardError::OutOfRange(Point { row: 0, col: 1 }))
         );
         assert!(
             board
@@ -548,7 +1025,7 @@ mod tests {
                 .can_put(
                     Stone::Black,
                     Point {
-                        row: BOARD_SIZE as i8 + 1,
+                        row: board.size() as i8 + 1,
                         col: 1
                     }
                 )
@@ -560,17 +1037,16 @@ mod tests {
                     Stone::Black,
                     Point {
                         row: 1,
-                        col: BOARD_SIZE as i8 + 1
+                        col: board.size() as i8 + 1
                     }
                 )
                 .is_err()
         );
 
         board.put(Stone::White, Point { row: 1, col: 1 }).unwrap();
-        assert!(
-            board
-                .can_put(Stone::White, Point { row: 1, col: 1 })
-                .is_err()
+        assert_eq!(
+            board.can_put(Stone::White, Point { row: 1, col: 1 }),
+            Err(BoardError::Occupied(Point { row: 1, col: 1 }))
         );
         assert!(
             board
@@ -585,7 +1061,7 @@ mod tests {
         // │ ① ┌─┬─○ ● ┬─
         // │ ② ├─○ ● ┼─●
         // │ ③ ├─┼─○ ● ┼─
-        let mut board = Board::new();
+        let mut board = Board::new(9);
         board.put(Stone::Black, Point { row: 1, col: 3 }).unwrap();
         board.put(Stone::Black, Point { row: 2, col: 2 }).unwrap();
         board.put(Stone::Black, Point { row: 3, col: 3 }).unwrap();
@@ -609,7 +1085,7 @@ mod tests {
         // │ ① ● ○ ┬─┬─┬─
         // │ ② ├─┼─┼─┼─┼─
         // │ ③ ├─┼─┼─┼─┼─
-        let mut board = Board::new();
+        let mut board = Board::new(9);
         board.put(Stone::White, Point { row: 1, col: 1 }).unwrap();
         board.put(Stone::Black, Point { row: 1, col: 2 }).unwrap();
 
@@ -625,7 +1101,7 @@ mod tests {
         // │ ① ┌ ○ ┬─┬─┬─
         // │ ② ○ ┼─┼─┼─┼─
         // │ ③ ├─┼─┼─┼─┼─
-        let mut expected = Board::new_with_prisoners(1, 0);
+        let mut expected = Board::new_with_prisoners(9, 1, 0);
         expected
             .put(Stone::Black, Point { row: 1, col: 2 })
             .unwrap();
@@ -640,7 +1116,7 @@ mod tests {
         // │ ① ┌ ○ ● ┬─┬─
         // │ ② ├─┼─○ ┼─┼─
         // │ ③ ├─┼─┼─┼─┼─
-        let mut board = Board::new();
+        let mut board = Board::new(9);
         board.put(Stone::Black, Point { row: 1, col: 2 }).unwrap();
         board.put(Stone::White, Point { row: 1, col: 3 }).unwrap();
         board.put(Stone::Black, Point { row: 2, col: 3 }).unwrap();
@@ -657,7 +1133,7 @@ mod tests {
         // │ ① ┌ ○ ┬─○ ┬─
         // │ ② ├─┼─○ ┼─┼─
         // │ ③ ├─┼─┼─┼─┼─
-        let mut expected = Board::new_with_prisoners(1, 0);
+        let mut expected = Board::new_with_prisoners(9, 1, 0);
         expected
             .put(Stone::Black, Point { row: 1, col: 2 })
             .unwrap();
@@ -675,14 +1151,14 @@ mod tests {
         // │ ① ┌─┬─○ ┬─┬─
         // │ ② ├─○ ● ○ ┼─
         // │ ③ ├─┼─○ ┼─┼─
-        let mut board = Board::new();
+        let mut board = Board::new(9);
         board.put(Stone::Black, Point { row: 1, col: 3 }).unwrap();
         board.put(Stone::Black, Point { row: 2, col: 2 }).unwrap();
         board.put(Stone::White, Point { row: 2, col: 3 }).unwrap();
         board.put(Stone::Black, Point { row: 2, col: 4 }).unwrap();
         board.put(Stone::Black, Point { row: 3, col: 3 }).unwrap();
 
-        let mut expected = Board::new_with_prisoners(1, 0);
+        let mut expected = Board::new_with_prisoners(9, 1, 0);
         expected
             .put(Stone::Black, Point { row: 1, col: 3 })
             .unwrap();
@@ -703,7 +1179,7 @@ mod tests {
         // │ ① ┌─┬─○ ○─┬─
         // │ ② ├─○ ● ● ○─
         // │ ③ ├─┼─○ ○─┼─
-        let mut board = Board::new();
+        let mut board = Board::new(9);
         board.put(Stone::Black, Point { row: 1, col: 3 }).unwrap();
         board.put(Stone::Black, Point { row: 1, col: 4 }).unwrap();
         board.put(Stone::Black, Point { row: 2, col: 2 }).unwrap();
@@ -713,7 +1189,7 @@ mod tests {
         board.put(Stone::Black, Point { row: 3, col: 3 }).unwrap();
         board.put(Stone::Black, Point { row: 3, col: 4 }).unwrap();
 
-        let mut expected = Board::new_with_prisoners(2, 0);
+        let mut expected = Board::new_with_prisoners(9, 2, 0);
         expected
             .put(Stone::Black, Point { row: 1, col: 3 })
             .unwrap();
@@ -735,11 +1211,22 @@ mod tests {
         assert_eq!(board, expected);
     }
 
+    // the group as points, in the order of the cell index
+    fn find_group(board: &Board, stone: Stone, point: Point) -> Vec<Point> {
+        let start = Bitboard::single(board.index(point));
+        board
+            .geometry
+            .flood(start, board.stones(stone))
+            .iter()
+            .map(|index| board.point_at(index))
+            .collect()
+    }
+
     #[test]
     fn board_find_group() {
         // no stone should be empty group.
-        let board = Board::new();
-        let group = board.find_group(Stone::Black, Point { row: 1, col: 1 });
+        let board = Board::new(9);
+        let group = find_group(&board, Stone::Black, Point { row: 1, col: 1 });
         assert_eq!(group, vec![]);
 
         // single stone should be group.
@@ -748,11 +1235,11 @@ mod tests {
         // │ ① ● ○ ┬─┬─┬─
         // │ ② ├─┼─┼─┼─┼─
         // │ ③ ├─┼─┼─┼─┼─
-        let mut board = Board::new();
+        let mut board = Board::new(9);
         board.put(Stone::White, Point { row: 1, col: 1 }).unwrap();
         board.put(Stone::Black, Point { row: 1, col: 2 }).unwrap();
 
-        let group = board.find_group(Stone::White, Point { row: 1, col: 1 });
+        let group = find_group(&board, Stone::White, Point { row: 1, col: 1 });
         assert_eq!(group, vec![Point { row: 1, col: 1 }]);
 
         // multiple stones should be group.
@@ -761,18 +1248,18 @@ mod tests {
         // │ ① ● ● ┬─┬─┬─
         // │ ② ├─┼─┼─┼─┼─
         // │ ③ ├─┼─┼─┼─┼─
-        let mut board = Board::new();
+        let mut board = Board::new(9);
         board.put(Stone::White, Point { row: 1, col: 1 }).unwrap();
         board.put(Stone::White, Point { row: 1, col: 2 }).unwrap();
 
-        let group = board.find_group(Stone::White, Point { row: 1, col: 1 });
+        let group = find_group(&board, Stone::White, Point { row: 1, col: 1 });
         assert_eq!(
             group,
             vec![Point { row: 1, col: 1 }, Point { row: 1, col: 2 }]
         );
 
         // and also can refer another point
-        let mut group = board.find_group(Stone::White, Point { row: 1, col: 2 });
+        let mut group = find_group(&board, Stone::White, Point { row: 1, col: 2 });
         group.sort_by_key(|p| p.col);
         assert_eq!(
             group,
@@ -785,7 +1272,7 @@ mod tests {
         // │ ① ● ● ┬─┬─┬─
         // │ ② ├─● ● ● ●
         // │ ③ ├─┼─● ┼─┼─
-        let mut board = Board::new();
+        let mut board = Board::new(9);
         board.put(Stone::White, Point { row: 1, col: 1 }).unwrap();
         board.put(Stone::White, Point { row: 1, col: 2 }).unwrap();
         board.put(Stone::White, Point { row: 2, col: 2 }).unwrap();
@@ -793,7 +1280,7 @@ mod tests {
         board.put(Stone::White, Point { row: 2, col: 4 }).unwrap();
         board.put(Stone::White, Point { row: 2, col: 5 }).unwrap();
         board.put(Stone::White, Point { row: 3, col: 3 }).unwrap();
-        let mut group = board.find_group(Stone::White, Point { row: 1, col: 2 });
+        let mut group = find_group(&board, Stone::White, Point { row: 1, col: 2 });
         group.sort_by_key(|p| p.col);
         group.sort_by_key(|p| p.row);
         assert_eq!(
@@ -810,6 +1297,66 @@ mod tests {
         )
     }
 
+    #[test]
+    fn board_group_at_and_liberties() {
+        // ┌─────────────
+        // │   ① ② ③ ④
+        // │ ① ● ● ○ ┬─
+        // │ ② ○ ┼─┼─┼─
+        // │ ③ ├─┼─┼─┼─
+        let mut board = Board::new(9);
+        board.put(Stone::White, Point { row: 1, col: 1 }).unwrap();
+        board.put(Stone::Black, Point { row: 1, col: 3 }).unwrap();
+        board.put(Stone::White, Point { row: 1, col: 2 }).unwrap();
+        board.put(Stone::Black, Point { row: 2, col: 1 }).unwrap();
+        let white = Point { row: 1, col: 2 };
+        assert_eq!(
+            board.group_at(white),
+            vec![Point { row: 1, col: 1 }, Point { row: 1, col: 2 }]
+        );
+        // white is in atari
+        assert_eq!(board.liberties(white), vec![Point { row: 2, col: 2 }]);
+        assert_eq!(board.liberty_count(white), 1);
+        assert_eq!(board.liberty_count(Point { row: 1, col: 3 }), 2);
+        // no group on empty points and out of the board
+        assert_eq!(board.group_at(Point { row: 2, col: 2 }), vec![]);
+        assert_eq!(board.liberties(Point { row: 0, col: 1 }), vec![]);
+        assert_eq!(board.liberty_count(Point { row: 2, col: 2 }), 0);
+
+        // black captures, and the chains breathe where white was
+        // ┌─────────────
+        // │   ① ② ③ ④
+        // │ ① ┌─┬─○ ┬─
+        // │ ② ○ ○ ┼─┼─
+        // │ ③ ├─┼─┼─┼─
+        board.put(Stone::Black, Point { row: 2, col: 2 }).unwrap();
+        assert_eq!(board.group_at(white), vec![]);
+        assert_eq!(
+            board.liberties(Point { row: 1, col: 3 }),
+            vec![
+                Point { row: 1, col: 2 },
+                Point { row: 1, col: 4 },
+                Point { row: 2, col: 3 }
+            ]
+        );
+        assert_eq!(
+            board.group_at(Point { row: 2, col: 1 }),
+            vec![Point { row: 2, col: 1 }, Point { row: 2, col: 2 }]
+        );
+        assert_eq!(board.liberty_count(Point { row: 2, col: 1 }), 5);
+
+        // the chains are joined
+        board.put(Stone::White, Point { row: 5, col: 5 }).unwrap();
+        board.put(Stone::Black, Point { row: 1, col: 2 }).unwrap();
+        assert_eq!(board.group_at(Point { row: 2, col: 1 }).len(), 4);
+        assert_eq!(board.liberty_count(Point { row: 1, col: 3 }), 5);
+
+        // and split again by undo
+        board.undo().unwrap();
+        assert_eq!(board.group_at(Point { row: 1, col: 3 }).len(), 1);
+        assert_eq!(board.liberty_count(Point { row: 1, col: 3 }), 3);
+    }
+
     #[test]
     fn test_is_suicide() {
         // ┌─────────────
@@ -817,15 +1364,14 @@ mod tests {
         // │ ① ┌─┬─○ ┬─┬─
         // │ ② ├─○ ┼─○ ┼─
         // │ ③ ├─┼─○ ┼─┼─
-        let mut board = Board::new();
+        let mut board = Board::new(9);
         board.put(Stone::Black, Point { row: 1, col: 3 }).unwrap();
         board.put(Stone::Black, Point { row: 2, col: 2 }).unwrap();
         board.put(Stone::Black, Point { row: 2, col: 4 }).unwrap();
         board.put(Stone::Black, Point { row: 3, col: 3 }).unwrap();
-        assert!(
-            board
-                .can_put(Stone::White, Point { row: 2, col: 3 })
-                .is_err()
+        assert_eq!(
+            board.can_put(Stone::White, Point { row: 2, col: 3 }),
+            Err(BoardError::Suicide(Point { row: 2, col: 3 }))
         );
     }
 
@@ -836,7 +1382,7 @@ mod tests {
         // │ ① ┌┬○●┬┬
         // │ ② ├○┼○●┼
         // │ ③ ├┼○●┼┼
-        let mut board = Board::new();
+        let mut board = Board::new(9);
         board.put(Stone::Black, Point { row: 1, col: 3 }).unwrap();
         board.put(Stone::Black, Point { row: 2, col: 2 }).unwrap();
         board.put(Stone::Black, Point { row: 2, col: 4 }).unwrap();
@@ -844,6 +1390,7 @@ mod tests {
         board.put(Stone::White, Point { row: 1, col: 4 }).unwrap();
         board.put(Stone::White, Point { row: 2, col: 5 }).unwrap();
         board.put(Stone::White, Point { row: 3, col: 4 }).unwrap();
+        assert_eq!(board.ko_point(), None);
 
         // ┌─────────────
         // │    ①②③④⑤⑥
@@ -853,11 +1400,11 @@ mod tests {
         board.put(Stone::White, Point { row: 2, col: 3 }).unwrap();
 
         // this is prohibited
-        assert!(
-            board
-                .can_put(Stone::Black, Point { row: 2, col: 4 })
-                .is_err()
+        assert_eq!(
+            board.can_put(Stone::Black, Point { row: 2, col: 4 }),
+            Err(BoardError::Ko(Point { row: 2, col: 4 }))
         );
+        assert_eq!(board.ko_point(), Some(Point { row: 2, col: 4 }));
 
         // this is ok because black done KO threat.
         // ┌─────────────
@@ -866,11 +1413,517 @@ mod tests {
         // │ ② ├○●┼●┼
         // │ ③ ├┼○●┼●
         board.put(Stone::Black, Point { row: 1, col: 1 }).unwrap();
+        assert_eq!(board.ko_point(), None);
         board.put(Stone::White, Point { row: 3, col: 6 }).unwrap();
         assert!(
             board
                 .can_put(Stone::Black, Point { row: 2, col: 4 })
                 .is_ok()
         );
+        assert_eq!(board.ko_point(), None);
+    }
+
+    #[test]
+    fn board_sizes() {
+        for size in [MIN_BOARD_SIZE, 9, 13, 19, MAX_BOARD_SIZE] {
+            let mut board = Board::new(size);
+            let last = size as i8;
+            assert!(
+                !board
+                    .get(Point {
+                        row: last,
+                        col: last
+                    })
+                    .is_wall()
+            );
+            assert!(
+                board
+                    .get(Point {
+                        row: last + 1,
+                        col: last
+                    })
+                    .is_wall()
+            );
+            assert!(
+                board
+                    .get(Point {
+                        row: last,
+                        col: last + 1
+                    })
+                    .is_wall()
+            );
+            assert_eq!(board.find_available_points(Stone::Black).len(), size * size);
+            board
+                .put(
+                    Stone::Black,
+                    Point {
+                        row: last,
+                        col: last,
+                    },
+                )
+                .unwrap();
+            assert_eq!(board.points().count(), size * size);
+        }
+
+        // capture at the far corner of a large board
+        let mut board = Board::new(19);
+        board.put(Stone::White, Point { row: 19, col: 19 }).unwrap();
+        board.put(Stone::Black, Point { row: 18, col: 19 }).unwrap();
+        board.put(Stone::Black, Point { row: 19, col: 18 }).unwrap();
+        assert!(board.get(Point { row: 19, col: 19 }).is_empty());
+        assert_eq!(board.black_prisoners, 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn board_size_too_large() {
+        Board::new(MAX_BOARD_SIZE + 1);
+    }
+
+    #[test]
+    fn board_star_points() {
+        assert_eq!(Board::new(5).star_points(), vec![]);
+        assert_eq!(Board::new(9).star_points().len(), 5);
+        assert_eq!(Board::new(13).star_points().len(), 9);
+        let star_points = Board::new(19).star_points();
+        assert_eq!(star_points.len(), 9);
+        assert!(star_points.contains(&Point { row: 4, col: 16 }));
+        assert!(star_points.contains(&Point { row: 10, col: 10 }));
+
+        // hoshi of 19x19 should be drawn
+        let display = Board::new(19).to_string();
+        assert_eq!(display.matches('•').count(), 9);
+        assert!(display.contains('⑲'));
+    }
+
+    // three kos on the board. black can take the kos at ② and ⑥ by ③,
+    // white can take the ko at ② by ⑥.
+    // ┌─────────────────────────┐
+    // │   ① ② ③ ④ ⑤ ⑥ ⑦ ⑧ ⑨   │
+    // │ ① ┌─○ ● ┬─┬─○ ● ┬─┐ ① │
+    // │ ② ○ ● ┼─● ○ ┼─○ ● ┤ ② │
+    // │ ③ ├─○ ● ┼─┼─○ ● ┼─┤ ③ │
+    // │ ④ ├─┼─┼─┼─┼─┼─┼─┼─┤ ④ │
+    // │ ⑤ ├─○ ● ┼─┼─┼─┼─┼─┤ ⑤ │
+    // │ ⑥ ○ ● ┼─● ┼─┼─┼─┼─┤ ⑥ │
+    // │ ⑦ ├─○ ● ┼─┼─┼─┼─┼─┤ ⑦ │
+    fn triple_ko_board(ko_rule: KoRule) -> Board {
+        let mut board = Board::new(9);
+        board.ko_rule = ko_rule;
+        for (row, col) in [(1, 2), (2, 1), (3, 2), (5, 2), (6, 1), (7, 2)] {
+            board.setup(Stone::Black, Point { row, col }).unwrap();
+        }
+        for (row, col) in [(1, 3), (2, 4), (3, 3), (5, 3), (6, 4), (7, 3)] {
+            board.setup(Stone::White, Point { row, col }).unwrap();
+        }
+        // black can take white at (2, 2) and (6, 2)
+        board.setup(Stone::White, Point { row: 2, col: 2 }).unwrap();
+        board.setup(Stone::White, Point { row: 6, col: 2 }).unwrap();
+        // white can take black at (2, 7)
+        for (row, col) in [(1, 6), (2, 5), (3, 6), (2, 7)] {
+            board.setup(Stone::Black, Point { row, col }).unwrap();
+        }
+        for (row, col) in [(1, 7), (2, 8), (3, 7)] {
+            board.setup(Stone::White, Point { row, col }).unwrap();
+        }
+        board
+    }
+
+    #[test]
+    fn triple_ko() {
+        let cycle = [
+            (Stone::Black, Point { row: 2, col: 3 }),
+            (Stone::White, Point { row: 2, col: 6 }),
+            (Stone::Black, Point { row: 6, col: 3 }),
+            (Stone::White, Point { row: 2, col: 2 }),
+            (Stone::Black, Point { row: 2, col: 7 }),
+            (Stone::White, Point { row: 6, col: 2 }),
+        ];
+
+        // simple ko lets the cycle repeat forever
+        let mut board = triple_ko_board(KoRule::Simple);
+        for _ in 0..3 {
+            for (stone, point) in cycle {
+                board.put(stone, point).unwrap();
+            }
+        }
+        assert_eq!(board.black_prisoners, 3 * 3);
+        assert_eq!(board.white_prisoners, 3 * 3);
+
+        // superko forbids the last move of the cycle
+        for ko_rule in [KoRule::PositionalSuperko, KoRule::SituationalSuperko] {
+            let mut board = triple_ko_board(ko_rule);
+            for (stone, point) in cycle[..5].iter() {
+                board.put(*stone, *point).unwrap();
+            }
+            let (stone, point) = cycle[5];
+            assert_eq!(board.can_put(stone, point), Err(BoardError::Superko(point)));
+            assert!(!board.find_available_points(stone).contains(&point));
+            assert!(board.put(stone, point).is_err());
+        }
+    }
+
+    #[test]
+    fn situational_superko() {
+        // the position after white's 4th move is made again by black's 7th move.
+        // ┌─────────
+        // │   ① ② ③
+        // │ ① ┌─○ ┐
+        // │ ② ● ● ○
+        // │ ③ └─┴─┘
+        let moves = [
+            (Stone::Black, Point { row: 1, col: 2 }),
+            (Stone::White, Point { row: 2, col: 1 }),
+            (Stone::Black, Point { row: 2, col: 3 }),
+            (Stone::White, Point { row: 2, col: 2 }),
+            (Stone::Black, Point { row: 1, col: 1 }),
+            (Stone::White, Point { row: 1, col: 3 }),
+        ];
+        let last = (Stone::Black, Point { row: 1, col: 2 });
+        for (ko_rule, is_legal) in [
+            (KoRule::Simple, true),
+            (KoRule::PositionalSuperko, false),
+            (KoRule::SituationalSuperko, true),
+        ] {
+            let mut board = Board::new(3);
+            board.ko_rule = ko_rule;
+            for (stone, point) in moves {
+                board.put(stone, point).unwrap();
+            }
+            assert_eq!(board.put(last.0, last.1).is_ok(), is_legal);
+        }
+    }
+
+    // hash of the board calculated from scratch
+    fn full_hash(board: &Board) -> u64 {
+        let stones = board
+            .points()
+            .filter_map(|p| match board.get(p) {
+                BoardCell::Space(Some(stone)) => Some(zobrist::key(board.index(p), stone)),
+                _ => None,
+            })
+            .fold(0, |hash, key| hash ^ key);
+        situation_hash(stones, board.side_to_move())
+    }
+
+    #[test]
+    fn board_hash() {
+        let empty = Board::new(9);
+        assert_eq!(empty.hash(), 0);
+
+        // same position by different orders has the same hash
+        let mut board1 = Board::new(9);
+        board1.put(Stone::Black, Point { row: 3, col: 3 }).unwrap();
+        board1.put(Stone::White, Point { row: 7, col: 7 }).unwrap();
+        board1.put(Stone::Black, Point { row: 3, col: 7 }).unwrap();
+        let mut board2 = Board::new(9);
+        board2.put(Stone::Black, Point { row: 3, col: 7 }).unwrap();
+        board2.put(Stone::White, Point { row: 7, col: 7 }).unwrap();
+        board2.put(Stone::Black, Point { row: 3, col: 3 }).unwrap();
+        assert_eq!(board1.hash(), board2.hash());
+        assert_eq!(board1.hash(), full_hash(&board1));
+
+        // side to move changes the hash
+        let before_pass = board1.hash();
+        board1.pass(Stone::White);
+        assert_ne!(board1.hash(), before_pass);
+        assert_eq!(board1.side_to_move(), Stone::Black);
+        assert_eq!(board1.hash(), full_hash(&board1));
+
+        // captured stones are removed from the hash
+        let mut board = Board::new(9);
+        board.put(Stone::White, Point { row: 1, col: 1 }).unwrap();
+        board.put(Stone::Black, Point { row: 1, col: 2 }).unwrap();
+        board.put(Stone::Black, Point { row: 2, col: 1 }).unwrap();
+        let mut expected = Board::new(9);
+        expected
+            .put(Stone::Black, Point { row: 1, col: 2 })
+            .unwrap();
+        expected
+            .put(Stone::Black, Point { row: 2, col: 1 })
+            .unwrap();
+        assert_eq!(board.hash(), expected.hash());
+        assert_eq!(board.hash(), full_hash(&board));
+
+        // checking moves does not change the hash
+        let hash = board.hash();
+        board.find_available_points(Stone::White);
+        assert_eq!(board.hash(), hash);
+    }
+
+    #[test]
+    fn board_suicide_allowed() {
+        // ┌─────────────
+        // │   ① ② ③ ④
+        // │ ① ┌─● ○ ┬─
+        // │ ② ● ● ○ ┼─
+        // │ ③ ○ ○ ┼─┼─
+        let mut board = Board::new(9);
+        board.suicide_allowed = true;
+        board.put(Stone::White, Point { row: 1, col: 2 }).unwrap();
+        board.put(Stone::White, Point { row: 2, col: 1 }).unwrap();
+        board.put(Stone::White, Point { row: 2, col: 2 }).unwrap();
+        board.put(Stone::Black, Point { row: 1, col: 3 }).unwrap();
+        board.put(Stone::Black, Point { row: 2, col: 3 }).unwrap();
+        board.put(Stone::Black, Point { row: 3, col: 1 }).unwrap();
+        board.put(Stone::Black, Point { row: 3, col: 2 }).unwrap();
+
+        // white fills its last breathing space and removes its own group
+        let hash = board.hash();
+        board.put(Stone::White, Point { row: 1, col: 1 }).unwrap();
+        assert_eq!(board.black_prisoners, 4);
+        assert_eq!(board.white_prisoners, 0);
+        for (row, col) in [(1, 1), (1, 2), (2, 1), (2, 2)] {
+            assert!(board.get(Point { row, col }).is_empty());
+        }
+        assert_ne!(board.hash(), hash);
+
+        // not a suicide when it kills
+        let mut board = Board::new(9);
+        board.suicide_allowed = true;
+        board.put(Stone::White, Point { row: 1, col: 2 }).unwrap();
+        board.put(Stone::Black, Point { row: 2, col: 1 }).unwrap();
+        board.put(Stone::Black, Point { row: 1, col: 3 }).unwrap();
+        board.put(Stone::Black, Point { row: 2, col: 2 }).unwrap();
+        board.put(Stone::Black, Point { row: 1, col: 1 }).unwrap();
+        assert_eq!(board.black_prisoners, 1);
+        assert_eq!(board.white_prisoners, 0);
+    }
+
+    #[test]
+    fn board_undo() {
+        // black captures white's two stones
+        // ┌─────────────
+        // │   ① ② ③ ④
+        // │ ① ● ● ○ ┬─
+        // │ ② ○ ○ ┼─┼─
+        let mut board = Board::new(9);
+        board.put(Stone::White, Point { row: 1, col: 1 }).unwrap();
+        board.put(Stone::Black, Point { row: 2, col: 1 }).unwrap();
+        board.put(Stone::White, Point { row: 1, col: 2 }).unwrap();
+        board.put(Stone::Black, Point { row: 2, col: 2 }).unwrap();
+        let before = board.clone();
+        let hash = board.hash();
+        board.put(Stone::Black, Point { row: 1, col: 3 }).unwrap();
+        assert_eq!(board.black_prisoners, 2);
+
+        board.undo().unwrap();
+        assert_eq!(board, before);
+        assert_eq!(board.hash(), hash);
+        assert_eq!(board.previous_positions, before.previous_positions);
+        assert_eq!(board.seen_positions, before.seen_positions);
+        assert!(board.get(Point { row: 1, col: 1 }).is_stone());
+
+        // a pass is taken back too
+        board.pass(Stone::Black);
+        board.undo().unwrap();
+        assert_eq!(board.hash(), hash);
+
+        // the initial position cannot be taken back
+        for _ in 0..4 {
+            board.undo().unwrap();
+        }
+        assert_eq!(board, Board::new(9));
+        assert_eq!(board.undo(), Err(BoardError::NoMoveToUndo));
+    }
+
+    #[test]
+    fn board_undo_ko_and_suicide() {
+        // ┌─────────────
+        // │   ① ② ③ ④
+        // │ ① ┌─○ ● ┬─
+        // │ ② ○ ● ┼─● ┼─
+        // │ ③ ├─○ ● ┼─
+        let mut board = Board::new(9);
+        board.put(Stone::Black, Point { row: 1, col: 2 }).unwrap();
+        board.put(Stone::White, Point { row: 1, col: 3 }).unwrap();
+        board.put(Stone::Black, Point { row: 2, col: 1 }).unwrap();
+        board.put(Stone::White, Point { row: 2, col: 2 }).unwrap();
+        board.put(Stone::Black, Point { row: 3, col: 2 }).unwrap();
+        board.put(Stone::White, Point { row: 2, col: 4 }).unwrap();
+        board.put(Stone::Black, Point { row: 5, col: 5 }).unwrap();
+        board.put(Stone::White, Point { row: 3, col: 3 }).unwrap();
+        // black takes the ko, white cannot take back at once
+        board.put(Stone::Black, Point { row: 2, col: 3 }).unwrap();
+        assert_eq!(board.ko_point(), Some(Point { row: 2, col: 2 }));
+        assert!(
+            board
+                .can_put(Stone::White, Point { row: 2, col: 2 })
+                .is_err()
+        );
+
+        // after taking back the capture, nothing is forbidden
+        board.undo().unwrap();
+        assert_eq!(board.ko_point(), None);
+        assert!(board.get(Point { row: 2, col: 2 }).is_stone());
+        assert!(
+            board
+                .can_put(Stone::Black, Point { row: 2, col: 3 })
+                .is_ok()
+        );
+
+        // suicide removes own stones, and undo puts them back
+        let mut board = Board::new(9);
+        board.ko_rule = KoRule::SituationalSuperko;
+        board.suicide_allowed = true;
+        board.put(Stone::White, Point { row: 1, col: 2 }).unwrap();
+        board.put(Stone::White, Point { row: 2, col: 1 }).unwrap();
+        let before = board.clone();
+        board.put(Stone::Black, Point { row: 1, col: 1 }).unwrap();
+        assert_eq!(board.white_prisoners, 1);
+        board.undo().unwrap();
+        assert_eq!(board, before);
+        assert_eq!(board.white_prisoners, 0);
+        assert_eq!(board.hash(), before.hash());
+    }
+
+    #[test]
+    fn board_kill_counts_group_once() {
+        // the white group touches the played point from two sides
+        // ┌─────────────
+        // │   ① ② ③ ④
+        // │ ① ● ● ○ ┬─
+        // │ ② ● ┼─┼─┼─
+        // │ ③ ○ ┼─┼─┼─
+        let mut board = Board::new(9);
+        board.put(Stone::White, Point { row: 1, col: 1 }).unwrap();
+        board.put(Stone::White, Point { row: 1, col: 2 }).unwrap();
+        board.put(Stone::White, Point { row: 2, col: 1 }).unwrap();
+        board.put(Stone::Black, Point { row: 1, col: 3 }).unwrap();
+        board.put(Stone::Black, Point { row: 3, col: 1 }).unwrap();
+        board.put(Stone::Black, Point { row: 2, col: 2 }).unwrap();
+        assert_eq!(board.black_prisoners, 3);
+        board.undo().unwrap();
+        assert_eq!(board.black_prisoners, 0);
+        assert!(board.get(Point { row: 2, col: 1 }).is_stone());
+    }
+
+    // every group on the board breathes, found point by point without bitboards
+    fn all_groups_breathe(board: &Board) -> bool {
+        board.points().all(|start| {
+            let BoardCell::Space(Some(stone)) = board.get(start) else {
+                return true;
+            };
+            let mut group = vec![start];
+            let mut i = 0;
+            while i < group.len() {
+                for p in group[i].neighbors() {
+                    if board.get(p).is_empty() {
+                        return true;
+                    }
+                    if board.get(p).is_same_color(stone) && !group.contains(&p) {
+                        group.push(p);
+                    }
+                }
+                i += 1;
+            }
+            false
+        })
+    }
+
+    // the chains kept by the moves are the groups found from scratch
+    fn assert_chains(board: &Board) {
+        let geometry = board.geometry;
+        let mut found = Bitboard::EMPTY;
+        let mut groups = 0;
+        for index in (board.black | board.white).iter() {
+            let stone = board.stone_at(index).unwrap();
+            let group = geometry.flood(Bitboard::single(index), board.stones(stone));
+            let chain = board.chains[board.chain_ids[index] as usize];
+            assert_eq!(chain.stones, group);
+            assert_eq!(
+                chain.liberties,
+                geometry.neighbors(group) & board.empty_points()
+            );
+            if !found.contains(index) {
+                found |= group;
+                groups += 1;
+            }
+        }
+        // the chains of removed stones are free
+        assert_eq!(board.chains.len() - board.free_chains.len(), groups);
+    }
+
+    #[test]
+    fn board_random_game() {
+        use rand::prelude::*;
+        // suicides take back chains which the move joined
+        for suicide_allowed in [false, true] {
+            let mut random = StdRng::seed_from_u64(1);
+            let mut board = Board::new(9);
+            board.ko_rule = KoRule::PositionalSuperko;
+            board.suicide_allowed = suicide_allowed;
+            let mut stone = Stone::Black;
+            let mut moves = 0;
+            for _ in 0..300 {
+                match board.find_available_points(stone).choose(&mut random) {
+                    Some(&point) => {
+                        board.put(stone, point).unwrap();
+                        moves += 1;
+                    }
+                    None => board.pass(stone),
+                }
+                stone = stone.flip();
+                assert!(all_groups_breathe(&board));
+                assert_chains(&board);
+                assert_eq!(board.hash(), full_hash(&board));
+                let stones = board.points().filter(|&p| board.get(p).is_stone()).count();
+                assert_eq!(
+                    stones + board.black_prisoners + board.white_prisoners,
+                    moves
+                );
+            }
+            assert!(board.black_prisoners + board.white_prisoners > 0);
+            let suicides = board.changes.iter().filter(|c| !c.self_captured.is_empty());
+            assert_eq!(suicides.count() > 0, suicide_allowed);
+
+            while board.undo().is_ok() {
+                assert_chains(&board);
+            }
+            assert_eq!(board, Board::new(9));
+            assert_eq!(board.hash(), 0);
+        }
+    }
+
+    #[test]
+    fn board_handicap_points() {
+        let board = Board::new(19);
+        // D4 and Q16
+        assert_eq!(
+            board.handicap_points(2).unwrap(),
+            vec![Point { row: 16, col: 4 }, Point { row: 4, col: 16 }]
+        );
+        for handicap in 2..=9 {
+            let points = board.handicap_points(handicap).unwrap();
+            assert_eq!(points.len(), handicap);
+            // every handicap stone is on a star point
+            assert!(points.iter().all(|p| board.star_points().contains(p)));
+            assert_eq!(points.iter().collect::<HashSet<_>>().len(), handicap);
+        }
+        // tengen only for odd handicaps
+        let tengen = Point { row: 10, col: 10 };
+        assert!(board.handicap_points(5).unwrap().contains(&tengen));
+        assert!(!board.handicap_points(6).unwrap().contains(&tengen));
+
+        let board = Board::new(9);
+        assert_eq!(board.handicap_points(9).unwrap().len(), 9);
+        assert!(
+            board
+                .handicap_points(4)
+                .unwrap()
+                .contains(&Point { row: 3, col: 3 })
+        );
+        assert_eq!(Board::new(13).handicap_points(9).unwrap().len(), 9);
+
+        assert_eq!(
+            board.handicap_points(1),
+            Err(BoardError::Handicap {
+                handicap: 1,
+                size: 9
+            })
+        );
+        assert!(board.handicap_points(10).is_err());
+        assert!(Board::new(10).handicap_points(5).is_err());
+        assert!(Board::new(5).handicap_points(2).is_err());
     }
 }
@@ -0,0 +1,265 @@
+// sets of points as bits of the cell index, row by row.
+// groups and their breathing spaces are found by shifting whole sets,
+// without visiting the points one by one or allocating.
+use crate::board::MAX_BOARD_SIZE;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not};
+
+const WORDS: usize = (MAX_BOARD_SIZE * MAX_BOARD_SIZE).div_ceil(64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Bitboard([u64; WORDS]);
+
+impl Bitboard {
+    pub const EMPTY: Bitboard = Bitboard([0; WORDS]);
+
+    pub fn single(index: usize) -> Self {
+        let mut bitboard = Self::EMPTY;
+        bitboard.insert(index);
+        bitboard
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        self.0[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    pub fn insert(&mut self, index: usize) {
+        self.0[index / 64] |= 1 << (index % 64);
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        self.0[index / 64] &= !(1 << (index % 64));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|&word| word == 0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    // indices in the set, in increasing order
+    pub fn iter(&self) -> Indices {
+        Indices {
+            words: self.0,
+            word: 0,
+        }
+    }
+}
+
+pub struct Indices {
+    words: [u64; WORDS],
+    word: usize,
+}
+
+impl Iterator for Indices {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.word < WORDS {
+            let bits = self.words[self.word];
+            if bits != 0 {
+                // clear the lowest bit
+                self.words[self.word] = bits & (bits - 1);
+                return Some(self.word * 64 + bits.trailing_zeros() as usize);
+            }
+            self.word += 1;
+        }
+        None
+    }
+}
+
+impl BitAnd for Bitboard {
+    type Output = Bitboard;
+
+    fn bitand(mut self, other: Bitboard) -> Bitboard {
+        self &= other;
+        self
+    }
+}
+
+impl BitAndAssign for Bitboard {
+    fn bitand_assign(&mut self, other: Bitboard) {
+        for (word, other) in self.0.iter_mut().zip(other.0) {
+            *word &= other;
+        }
+    }
+}
+
+impl BitOr for Bitboard {
+    type Output = Bitboard;
+
+    fn bitor(mut self, other: Bitboard) -> Bitboard {
+        self |= other;
+        self
+    }
+}
+
+impl BitOrAssign for Bitboard {
+    fn bitor_assign(&mut self, other: Bitboard) {
+        for (word, other) in self.0.iter_mut().zip(other.0) {
+            *word |= other;
+        }
+    }
+}
+
+// the complement includes the indices out of the board, use it with `&`
+impl Not for Bitboard {
+    type Output = Bitboard;
+
+    fn not(self) -> Bitboard {
+        Bitboard(self.0.map(|word| !word))
+    }
+}
+
+// the shape of a board size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Geometry {
+    size: usize,
+    // words holding the points, the rest are always zero
+    words: usize,
+    // all points of the board
+    all: Bitboard,
+    // a shift to the right moves the last column onto the first column of the next row
+    not_first_col: Bitboard,
+    not_last_col: Bitboard,
+}
+
+impl Geometry {
+    pub fn new(size: usize) -> Self {
+        let mut geometry = Geometry {
+            size,
+            words: (size * size).div_ceil(64),
+            all: Bitboard::EMPTY,
+            not_first_col: Bitboard::EMPTY,
+            not_last_col: Bitboard::EMPTY,
+        };
+        for index in 0..size * size {
+            geometry.all.insert(index);
+            if index % size != 0 {
+                geometry.not_first_col.insert(index);
+            }
+            if index % size != size - 1 {
+                geometry.not_last_col.insert(index);
+            }
+        }
+        geometry
+    }
+
+    pub fn all(&self) -> Bitboard {
+        self.all
+    }
+
+    // points next to the set, not in the set
+    pub fn neighbors(&self, set: Bitboard) -> Bitboard {
+        let (bits, size) = (&set.0, self.size);
+        let mut neighbors = [0; WORDS];
+        // shift the words by one point for the left and right, and by a row for the up and down.
+        // the bits shifted out of a word move into the next word
+        for i in 0..self.words {
+            let previous = if i > 0 { bits[i - 1] } else { 0 };
+            let next = if i + 1 < WORDS { bits[i + 1] } else { 0 };
+            let right = (bits[i] << 1 | previous >> 63) & self.not_first_col.0[i];
+            let left = (bits[i] >> 1 | next << 63) & self.not_last_col.0[i];
+            let down = bits[i] << size | previous >> (64 - size);
+            let up = bits[i] >> size | next << (64 - size);
+            neighbors[i] = (right | left | down | up) & self.all.0[i] & !bits[i];
+        }
+        Bitboard(neighbors)
+    }
+
+    // points of `within` connected to the start, including the start
+    pub fn flood(&self, start: Bitboard, within: Bitboard) -> Bitboard {
+        let mut group = start & within;
+        loop {
+            let grown = group | (self.neighbors(group) & within);
+            if grown == group {
+                return group;
+            }
+            group = grown;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bitboard(indices: &[usize]) -> Bitboard {
+        let mut bitboard = Bitboard::EMPTY;
+        for &index in indices {
+            bitboard.insert(index);
+        }
+        bitboard
+    }
+
+    #[test]
+    fn bitboard_set_operations() {
+        let set = bitboard(&[0, 63, 64, 624]);
+        assert_eq!(set.len(), 4);
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![0, 63, 64, 624]);
+        assert!(set.contains(64));
+        assert!(!set.contains(65));
+        assert_eq!(set & bitboard(&[63, 65]), Bitboard::single(63));
+        assert_eq!((set | Bitboard::single(1)).len(), 5);
+
+        let mut set = set;
+        set.remove(0);
+        set.remove(0);
+        assert_eq!(set.iter().next(), Some(63));
+        assert!(Bitboard::EMPTY.is_empty());
+        assert_eq!(Bitboard::EMPTY.iter().next(), None);
+    }
+
+    #[test]
+    fn geometry_neighbors() {
+        // 3x3
+        // 0 1 2
+        // 3 4 5
+        // 6 7 8
+        let geometry = Geometry::new(3);
+        assert_eq!(
+            geometry.neighbors(Bitboard::single(4)),
+            bitboard(&[1, 3, 5, 7])
+        );
+        // no wraparound over the edges
+        assert_eq!(geometry.neighbors(Bitboard::single(2)), bitboard(&[1, 5]));
+        assert_eq!(
+            geometry.neighbors(Bitboard::single(3)),
+            bitboard(&[0, 4, 6])
+        );
+        assert_eq!(geometry.neighbors(Bitboard::single(8)), bitboard(&[5, 7]));
+        assert_eq!(geometry.neighbors(bitboard(&[0, 1])), bitboard(&[2, 3, 4]));
+
+        // across the words of the largest board
+        let geometry = Geometry::new(MAX_BOARD_SIZE);
+        assert_eq!(
+            geometry.neighbors(Bitboard::single(64)),
+            bitboard(&[39, 63, 65, 89])
+        );
+        assert_eq!(
+            geometry.neighbors(Bitboard::single(624)),
+            bitboard(&[599, 623])
+        );
+        assert_eq!(geometry.all().len(), 625);
+    }
+
+    #[test]
+    fn geometry_flood() {
+        // 4x4, the stones are x
+        // x x . .
+        // . x . x
+        // . x . x
+        // x . . .
+        let geometry = Geometry::new(4);
+        let stones = bitboard(&[0, 1, 5, 7, 9, 11, 12]);
+        let group = geometry.flood(Bitboard::single(0), stones);
+        assert_eq!(group, bitboard(&[0, 1, 5, 9]));
+        assert_eq!(
+            geometry.flood(Bitboard::single(11), stones),
+            bitboard(&[7, 11])
+        );
+        // the start out of the set is empty
+        assert!(geometry.flood(Bitboard::single(2), stones).is_empty());
+    }
+}
@@ -0,0 +1,63 @@
+// Zobrist hashing keys.
+// keys are derived from the cell index instead of a random table,
+// so every board of every size shares them without any allocation.
+use crate::board::Stone;
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+
+// xor-ed into the hash while white is to move
+pub const WHITE_TO_MOVE: u64 = splitmix64(u64::MAX);
+
+pub fn key(index: usize, stone: Stone) -> u64 {
+    let color = match stone {
+        Stone::Black => 0,
+        Stone::White => 1,
+    };
+    splitmix64((index * 2 + color) as u64)
+}
+
+// maps keyed by hashes. the keys are random already, hashing them again is a waste
+pub type HashMapByHash<V> = HashMap<u64, V, BuildHasherDefault<IdentityHasher>>;
+
+#[derive(Debug, Default)]
+pub struct IdentityHasher(u64);
+
+impl Hasher for IdentityHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = self.0.rotate_left(8) ^ byte as u64;
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.0 = value;
+    }
+}
+
+// https://prng.di.unimi.it/splitmix64.c
+const fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn zobrist_keys_are_unique() {
+        let mut keys = HashSet::new();
+        for index in 0..25 * 25 {
+            assert!(keys.insert(key(index, Stone::Black)));
+            assert!(keys.insert(key(index, Stone::White)));
+        }
+        assert!(keys.insert(WHITE_TO_MOVE));
+    }
+}
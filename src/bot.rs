@@ -1,5 +1,18 @@
 pub mod bot_trait;
+pub mod mcts;
 pub mod random;
 
-pub use bot_trait::Bot;
+pub use bot_trait::{Bot, settle_dead_stones};
+pub use mcts::{MctsBot, SearchBudget};
 pub use random::RandomBot;
+
+// bots which can be chosen by name, e.g. on the command line
+pub const BOT_NAMES: [&str; 2] = ["random", "mcts"];
+
+pub fn create_bot(name: &str) -> Option<Box<dyn Bot>> {
+    match name {
+        "random" => Some(Box::new(RandomBot::new())),
+        "mcts" => Some(Box::new(MctsBot::new())),
+        _ => None,
+    }
+}
@@ -1,6 +1,35 @@
-use crate::game::{Command, Game};
+use crate::board::{Point, Stone};
+use crate::game::{Command, Game, GameStatus};
+use std::collections::HashSet;
 
 pub trait Bot {
-    fn new() -> Self;
+    fn new() -> Self
+    where
+        Self: Sized;
+    // the time the bot can spend is `game.time_for_move()` when the game has a clock
     fn next_command(&mut self, game: &Game) -> Command;
+
+    // stones the bot regards as dead after both players passed. all alive by default
+    fn dead_stones(&mut self, _game: &Game) -> HashSet<Point> {
+        HashSet::new()
+    }
+}
+
+// end the scoring phase of a game between bots. stones both bots regard as dead
+// are removed, and disputed stones stay alive
+pub fn settle_dead_stones(game: &mut Game, first: &mut dyn Bot, second: &mut dyn Bot) {
+    if game.status != GameStatus::Scoring {
+        return;
+    }
+    let dead_stones = first.dead_stones(game);
+    for point in second.dead_stones(game) {
+        if dead_stones.contains(&point) {
+            // a point without a stone is ignored
+            game.set_dead(point, true).ok();
+        }
+    }
+    for player in [Stone::Black, Stone::White] {
+        game.agree(player)
+            .expect("the game is in the scoring phase");
+    }
 }
@@ -0,0 +1,396 @@
+use crate::board::{Board, BoardCell, Point, Stone};
+use crate::bot::Bot;
+use crate::bot::random::find_candidate_points;
+use crate::clock::{TimeSource, WallTime};
+use crate::game::{Command, Game, GameStatus};
+use crate::score;
+use rand::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+// weight of exploration in UCT. sqrt(2) in theory
+const EXPLORATION: f32 = 1.4;
+const DEFAULT_PLAYOUTS: usize = 1000;
+// with a clock, a move takes at most this fraction of the time for the move,
+// keeping the rest for the following moves
+const TIME_FRACTION: u32 = 10;
+// playouts to judge which stones are dead at the end of the game
+const DEAD_STONE_PLAYOUTS: usize = 100;
+
+// how long the bot searches for a command
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchBudget {
+    Playouts(usize),
+    Time(Duration),
+}
+
+// monte carlo tree search with UCT and random playouts
+pub struct MctsBot {
+    random_generator: StdRng,
+    seed: u64,
+    budget: SearchBudget,
+    // the time of a time budget is read from here
+    time_source: Arc<dyn TimeSource + Send + Sync>,
+}
+
+// node of the search tree, reached by the command of the player
+struct Node {
+    player: Stone,
+    command: Command,
+    children: Vec<usize>,
+    untried_commands: Vec<Command>,
+    visits: u32,
+    // 1 for each win of the player, 0.5 for each draw
+    wins: f32,
+}
+
+impl Node {
+    fn new(player: Stone, command: Command, untried_commands: Vec<Command>) -> Self {
+        Node {
+            player,
+            command,
+            children: vec![],
+            untried_commands,
+            visits: 0,
+            wins: 0.0,
+        }
+    }
+
+    fn uct(&self, parent_visits: u32) -> f32 {
+        let visits = self.visits as f32;
+        self.wins / visits + EXPLORATION * ((parent_visits as f32).ln() / visits).sqrt()
+    }
+}
+
+impl Bot for MctsBot {
+    fn new() -> Self {
+        MctsBot::with_budget(SearchBudget::Playouts(DEFAULT_PLAYOUTS))
+    }
+
+    fn next_command(&mut self, game: &Game) -> Command {
+        let tree = self.search(game);
+        // the most visited command is the most reliable
+        tree[0]
+            .children
+            .iter()
+            .max_by_key(|&&child| tree[child].visits)
+            .map_or(Command::Pass, |&child| tree[child].command)
+    }
+
+    // stones in the area of the opponent at the end of most random playouts
+    fn dead_stones(&mut self, game: &Game) -> HashSet<Point> {
+        let mut lost = HashMap::new();
+        for _ in 0..DEAD_STONE_PLAYOUTS {
+            let board = self.play_out(&game.board, game.turn);
+            let territory = score::find_territory(&board, &HashSet::new());
+            for point in game.board.points() {
+                let BoardCell::Space(Some(stone)) = game.board.get(point) else {
+                    continue;
+                };
+                let owner = match board.get(point) {
+                    BoardCell::Space(Some(owner)) => Some(owner),
+                    _ if territory.black.contains(&point) => Some(Stone::Black),
+                    _ if territory.white.contains(&point) => Some(Stone::White),
+                    _ => None,
+                };
+                if owner == Some(stone.flip()) {
+                    *lost.entry(point).or_insert(0) += 1;
+                }
+            }
+        }
+        lost.into_iter()
+            .filter(|&(_, count)| count * 2 > DEAD_STONE_PLAYOUTS)
+            .map(|(point, _)| point)
+            .collect()
+    }
+}
+
+impl MctsBot {
+    pub fn with_budget(budget: SearchBudget) -> Self {
+        MctsBot::with_seed(budget, rand::random())
+    }
+
+    // the same seed plays the same moves with a playout budget.
+    // a time budget depends on the speed of the machine.
+    pub fn with_seed(budget: SearchBudget, seed: u64) -> Self {
+        MctsBot {
+            random_generator: StdRng::seed_from_u64(seed),
+            seed,
+            budget,
+            time_source: Arc::new(WallTime::new()),
+        }
+    }
+
+    pub fn set_time_source(&mut self, source: Arc<dyn TimeSource + Send + Sync>) {
+        self.time_source = source;
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn search(&mut self, game: &Game) -> Vec<Node> {
+        let root = Node::new(game.turn.flip(), Command::Pass, candidate_commands(game));
+        let mut tree = vec![root];
+        // moves of each search are taken back after the search
+        let mut virtual_game = virtual_game(game);
+        let time_limit = game.time_for_move().map(|time| time / TIME_FRACTION);
+        let start = self.time_source.now();
+        let mut playouts = 0;
+        while !self.is_budget_over(playouts, start, time_limit) {
+            self.search_once(&mut tree, &mut virtual_game);
+            // handicap stones cannot be taken back
+            if virtual_game.setup.len() != game.setup.len() {
+                virtual_game = self::virtual_game(game);
+            }
+            playouts += 1;
+        }
+        tree
+    }
+
+    fn is_budget_over(
+        &self,
+        playouts: usize,
+        start: Duration,
+        time_limit: Option<Duration>,
+    ) -> bool {
+        let elapsed = self.time_source.now().saturating_sub(start);
+        if time_limit.is_some_and(|time| elapsed >= time) {
+            return true;
+        }
+        match self.budget {
+            SearchBudget::Playouts(max_playouts) => playouts >= max_playouts,
+            SearchBudget::Time(time) => elapsed >= time,
+        }
+    }
+
+    fn search_once(&mut self, tree: &mut Vec<Node>, virtual_game: &mut Game) {
+        let moves = virtual_game.moves.len();
+        let mut node = 0;
+        let mut path = vec![node];
+
+        // selection: follow the best child until a node with untried commands
+        while tree[node].untried_commands.is_empty() && !tree[node].children.is_empty() {
+            let parent_visits = tree[node].visits;
+            node = *tree[node]
+                .children
+                .iter()
+                .max_by(|&&a, &&b| {
+                    tree[a]
+                        .uct(parent_visits)
+                        .total_cmp(&tree[b].uct(parent_visits))
+                })
+                .unwrap_or(&node);
+            virtual_game
+                .play(tree[node].command)
+                .expect("commands in the tree are legal");
+            path.push(node);
+        }
+
+        // expansion: add a child by one of the untried commands
+        if !tree[node].untried_commands.is_empty() {
+            let index = self
+                .random_generator
+                .random_range(0..tree[node].untried_commands.len());
+            let command = tree[node].untried_commands.swap_remove(index);
+            let player = virtual_game.turn;
+            virtual_game
+                .play(command)
+                .expect("candidate commands are legal");
+            tree.push(Node::new(player, command, candidate_commands(virtual_game)));
+            let child = tree.len() - 1;
+            tree[node].children.push(child);
+            path.push(child);
+        }
+
+        // simulation and backpropagation
+        let winner = self.playout(virtual_game);
+        while virtual_game.moves.len() > moves {
+            virtual_game
+                .undo()
+                .expect("commands in the tree were played");
+        }
+        for node in path {
+            tree[node].visits += 1;
+            tree[node].wins += match winner {
+                Some(stone) if stone == tree[node].player => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+        }
+    }
+
+    // play random moves until both players pass. the winner by area scoring, None for a draw.
+    fn playout(&mut self, game: &Game) -> Option<Stone> {
+        let board = if game.status == GameStatus::Continue {
+            self.play_out(&game.board, game.turn)
+        } else {
+            game.board.clone()
+        };
+        let (black, white) = score::area_score(&board, game.ruleset().komi, &HashSet::new());
+        if black.total() > white.total() {
+            Some(Stone::Black)
+        } else if black.total() < white.total() {
+            Some(Stone::White)
+        } else {
+            None
+        }
+    }
+
+    // the board after random moves from the board until both players pass
+    fn play_out(&mut self, board: &Board, mut turn: Stone) -> Board {
+        let mut board = board.clone();
+        let mut pass_count = 0;
+        // in case of long cycles of captures
+        let max_moves = board.size() * board.size() * 3;
+        let mut moves = 0;
+        while pass_count < 2 && moves < max_moves {
+            if self.put_random_stone(&mut board, turn) {
+                pass_count = 0;
+            } else {
+                board.pass(turn);
+                pass_count += 1;
+            }
+            turn = turn.flip();
+            moves += 1;
+        }
+        board
+    }
+
+    // faster than finding all available points: try empty points in random order
+    fn put_random_stone(&mut self, board: &mut Board, stone: Stone) -> bool {
+        let mut points = board
+            .points()
+            .filter(|&p| board.get(p).is_empty())
+            .collect::<Vec<_>>();
+        points.shuffle(&mut self.random_generator);
+        points
+            .into_iter()
+            .any(|p| !board.is_eye(stone, p) && board.put(stone, p).is_ok())
+    }
+}
+
+// a copy of the game to search on. its moves must not run the clock
+fn virtual_game(game: &Game) -> Game {
+    let mut virtual_game = game.clone();
+    virtual_game.clock = None;
+    virtual_game
+}
+
+fn candidate_commands(game: &Game) -> Vec<Command> {
+    if game.status != GameStatus::Continue {
+        return vec![];
+    }
+    let commands = find_candidate_points(&game.board, game.turn)
+        .into_iter()
+        .map(|point| Command::Move {
+            stone: game.turn,
+            point,
+        })
+        .collect::<Vec<_>>();
+    // pass only when there is nothing to do, as RandomBot
+    if commands.is_empty() {
+        vec![Command::Pass]
+    } else {
+        commands
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, MockTime, TimeControl};
+    use crate::rules::Ruleset;
+    use std::sync::Mutex;
+
+    // a millisecond passes each time the time is read
+    #[derive(Debug, Default)]
+    struct TickingTime {
+        now: Mutex<Duration>,
+    }
+
+    impl TimeSource for TickingTime {
+        fn now(&self) -> Duration {
+            let mut now = self.now.lock().unwrap();
+            *now += Duration::from_millis(1);
+            *now
+        }
+    }
+
+    #[test]
+    fn mcts_search_expands_all_candidates() {
+        let game = Game::new(5, Ruleset::default());
+        let mut bot = MctsBot::with_budget(SearchBudget::Playouts(40));
+        let tree = bot.search(&game);
+
+        assert_eq!(tree[0].visits, 40);
+        // every playout goes through one of the children of the root
+        let children = &tree[0].children;
+        assert_eq!(children.iter().map(|&c| tree[c].visits).sum::<u32>(), 40);
+        // all 25 points are tried before any point is searched deeper
+        assert_eq!(children.len(), 25);
+        assert!(tree[0].untried_commands.is_empty());
+        for &child in children {
+            assert_eq!(tree[child].player, Stone::Black);
+            assert!(tree[child].wins <= tree[child].visits as f32);
+        }
+    }
+
+    #[test]
+    fn mcts_same_seed_same_command() {
+        let mut game = Game::new(5, Ruleset::default());
+        for _ in 0..3 {
+            let seed = rand::random();
+            let command = MctsBot::with_seed(SearchBudget::Playouts(30), seed).next_command(&game);
+            let replayed = MctsBot::with_seed(SearchBudget::Playouts(30), seed).next_command(&game);
+            assert_eq!(command, replayed, "seed: {}", seed);
+            game.play(command).unwrap();
+        }
+    }
+
+    #[test]
+    fn mcts_time_budget() {
+        let mut game = Game::new(9, Ruleset::default());
+        let mut bot = MctsBot::with_budget(SearchBudget::Time(Duration::from_millis(50)));
+        bot.set_time_source(Arc::new(TickingTime::default()));
+        // the time is read once before the search and once before each playout
+        assert_eq!(bot.search(&game)[0].visits, 49);
+        let command = bot.next_command(&game);
+        assert!(matches!(
+            command,
+            Command::Move {
+                stone: Stone::Black,
+                ..
+            }
+        ));
+        assert!(game.play(command).is_ok());
+    }
+
+    #[test]
+    fn mcts_budgets_time_of_clock() {
+        let mut game = Game::new(9, Ruleset::default());
+        game.clock = Some(Clock::new(
+            TimeControl::Absolute {
+                main: Duration::from_millis(500),
+            },
+            Arc::new(MockTime::default()),
+        ));
+        // the playouts would never end without the clock
+        let mut bot = MctsBot::with_budget(SearchBudget::Playouts(usize::MAX));
+        bot.set_time_source(Arc::new(TickingTime::default()));
+        // a tenth of the time for the move
+        assert_eq!(bot.search(&game)[0].visits, 49);
+        let command = bot.next_command(&game);
+        assert!(game.play(command).is_ok());
+    }
+
+    #[test]
+    fn mcts_passes_at_game_end() {
+        let mut game = Game::new(9, Ruleset::default());
+        game.play(Command::Pass).unwrap();
+        game.play(Command::Pass).unwrap();
+        let mut bot = MctsBot::with_budget(SearchBudget::Playouts(10));
+        assert_eq!(bot.next_command(&game), Command::Pass);
+    }
+}
@@ -1,28 +1,23 @@
+use crate::board::{Board, Point, Stone};
 use crate::bot::Bot;
 use crate::game::{Command, Game};
 use rand::prelude::*;
 
 pub struct RandomBot {
-    random_generator: ThreadRng,
+    random_generator: StdRng,
+    seed: u64,
 }
 
 impl Bot for RandomBot {
     fn new() -> Self {
-        RandomBot {
-            random_generator: rand::rng(),
-        }
+        RandomBot::with_seed(rand::random())
     }
 
     fn next_command(&mut self, game: &Game) -> Command {
-        // to prevent mutation of the game, clone the game
-        let mut virtual_game = game.clone();
-        // todo: refactor not to use Board directly
-        let available_points = virtual_game.board.find_available_points(game.turn);
-        let available_points = available_points
+        let available_points = find_candidate_points(&game.board, game.turn);
+        let random_point = available_points
             .into_iter()
-            .filter(|&p| !virtual_game.board.is_eye(game.turn, p));
-        // check if point fills my territory
-        let random_point = available_points.choose(&mut self.random_generator);
+            .choose(&mut self.random_generator);
         // make new command.
         // if there is some available point, put stone.
         // if no, pass the turn
@@ -35,3 +30,57 @@ impl Bot for RandomBot {
         }
     }
 }
+
+impl RandomBot {
+    // the same seed plays the same moves in the same games
+    pub fn with_seed(seed: u64) -> Self {
+        RandomBot {
+            random_generator: StdRng::seed_from_u64(seed),
+            seed,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+// points where the stone can be put, except the own eyes.
+// filling own eyes only makes the own groups die.
+pub fn find_candidate_points(board: &Board, stone: Stone) -> Vec<Point> {
+    // todo: refactor not to use Board directly
+    board
+        .find_available_points(stone)
+        .into_iter()
+        .filter(|&p| !board.is_eye(stone, p))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameStatus;
+    use crate::rules::Ruleset;
+
+    fn play_game(black_seed: u64, white_seed: u64) -> Vec<(Stone, Command)> {
+        let mut game = Game::new(7, Ruleset::default());
+        let mut black = RandomBot::with_seed(black_seed);
+        let mut white = RandomBot::with_seed(white_seed);
+        while game.status == GameStatus::Continue {
+            let command = match game.turn {
+                Stone::Black => black.next_command(&game),
+                Stone::White => white.next_command(&game),
+            };
+            game.play(command).unwrap();
+        }
+        game.moves
+    }
+
+    #[test]
+    fn random_bot_replays_game_by_seed() {
+        let moves = play_game(1, 2);
+        assert_eq!(play_game(1, 2), moves);
+        assert_ne!(play_game(2, 1), moves);
+        assert_eq!(RandomBot::with_seed(3).seed(), 3);
+    }
+}
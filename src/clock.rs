@@ -0,0 +1,305 @@
+// game clocks of both players. the time is read from a TimeSource,
+// so tests can move a mock time instead of waiting.
+use crate::board::Stone;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeControl {
+    // the game is lost when the main time runs out
+    Absolute {
+        main: Duration,
+    },
+    // after the main time, every move has to be played within a period.
+    // a period is lost each time it is overrun, and the game when none is left
+    ByoYomi {
+        main: Duration,
+        period: Duration,
+        periods: usize,
+    },
+    // after the main time, the given number of stones have to be played within each period
+    Canadian {
+        main: Duration,
+        period: Duration,
+        stones: usize,
+    },
+}
+
+// time left of a player
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayerTime {
+    pub main: Duration,
+    // byo-yomi: the length of a period. canadian: time left in the current period
+    pub period: Duration,
+    // byo-yomi periods left
+    pub periods: usize,
+    // stones to play in the current canadian period
+    pub stones: usize,
+}
+
+pub trait TimeSource: fmt::Debug {
+    // time passed from any fixed point
+    fn now(&self) -> Duration;
+}
+
+// the real time
+#[derive(Debug)]
+pub struct WallTime {
+    start: Instant,
+}
+
+impl WallTime {
+    pub fn new() -> Self {
+        WallTime {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for WallTime {
+    fn default() -> Self {
+        WallTime::new()
+    }
+}
+
+impl TimeSource for WallTime {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+// time which passes only by `advance`
+#[derive(Debug, Default)]
+pub struct MockTime {
+    now: Mutex<Duration>,
+}
+
+impl MockTime {
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+}
+
+impl TimeSource for MockTime {
+    fn now(&self) -> Duration {
+        *self.now.lock().unwrap()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Clock {
+    pub control: TimeControl,
+    pub black: PlayerTime,
+    pub white: PlayerTime,
+    source: Arc<dyn TimeSource + Send + Sync>,
+    // when the player to move started thinking
+    turn_start: Duration,
+}
+
+impl Clock {
+    // the clock of the player to move starts now.
+    // canadian overtime without stones or time to play them is absolute time
+    pub fn new(control: TimeControl, source: Arc<dyn TimeSource + Send + Sync>) -> Self {
+        let control = match control {
+            TimeControl::Canadian {
+                main,
+                period,
+                stones,
+            } if stones == 0 || period.is_zero() => TimeControl::Absolute { main },
+            _ => control,
+        };
+        let time = match control {
+            TimeControl::Absolute { main } => PlayerTime {
+                main,
+                period: Duration::ZERO,
+                periods: 0,
+                stones: 0,
+            },
+            TimeControl::ByoYomi {
+                main,
+                period,
+                periods,
+            } => PlayerTime {
+                main,
+                period,
+                periods,
+                stones: 0,
+            },
+            TimeControl::Canadian {
+                main,
+                period,
+                stones,
+            } => PlayerTime {
+                main,
+                period,
+                periods: 0,
+                stones,
+            },
+        };
+        Clock {
+            control,
+            black: time,
+            white: time,
+            turn_start: source.now(),
+            source,
+        }
+    }
+
+    pub fn time(&self, stone: Stone) -> PlayerTime {
+        match stone {
+            Stone::Black => self.black,
+            Stone::White => self.white,
+        }
+    }
+
+    // time spent on the current move so far
+    pub fn elapsed(&self) -> Duration {
+        self.source.now().saturating_sub(self.turn_start)
+    }
+
+    // how long the player to move can still think on this move without losing the game,
+    // or a byo-yomi period
+    pub fn time_for_move(&self, stone: Stone) -> Duration {
+        let time = self.time(stone);
+        let overtime = match self.control {
+            TimeControl::Absolute { .. } => Duration::ZERO,
+            TimeControl::ByoYomi { .. } if time.periods == 0 => Duration::ZERO,
+            TimeControl::ByoYomi { .. } | TimeControl::Canadian { .. } => time.period,
+        };
+        (time.main + overtime).saturating_sub(self.elapsed())
+    }
+
+    // time left of the player after the current move is played now. None if the time is over
+    pub fn charge(&self, stone: Stone) -> Option<PlayerTime> {
+        let mut time = self.time(stone);
+        let mut elapsed = self.elapsed();
+        if elapsed <= time.main {
+            time.main -= elapsed;
+            return Some(time);
+        }
+        elapsed -= time.main;
+        time.main = Duration::ZERO;
+        match self.control {
+            TimeControl::Absolute { .. } => None,
+            TimeControl::ByoYomi { period, .. } => {
+                // every overrun period is lost
+                while time.periods > 0 {
+                    if elapsed <= period {
+                        return Some(time);
+                    }
+                    elapsed -= period;
+                    time.periods -= 1;
+                }
+                None
+            }
+            TimeControl::Canadian { period, stones, .. } => {
+                if elapsed > time.period {
+                    return None;
+                }
+                time.period -= elapsed;
+                time.stones -= 1;
+                // a new period starts after enough stones
+                if time.stones == 0 {
+                    time.period = period;
+                    time.stones = stones;
+                }
+                Some(time)
+            }
+        }
+    }
+
+    // record the time left after the move and start the clock of the next player
+    pub fn punch(&mut self, stone: Stone, time: PlayerTime) {
+        match stone {
+            Stone::Black => self.black = time,
+            Stone::White => self.white = time,
+        }
+        self.turn_start = self.source.now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seconds(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    // the clock of black after black thinks for each of the durations
+    fn play(control: TimeControl, moves: &[u64]) -> Option<PlayerTime> {
+        let source = Arc::new(MockTime::default());
+        let mut clock = Clock::new(control, source.clone());
+        for &duration in moves {
+            source.advance(seconds(duration));
+            let time = clock.charge(Stone::Black)?;
+            clock.punch(Stone::Black, time);
+        }
+        Some(clock.black)
+    }
+
+    #[test]
+    fn clock_absolute() {
+        let control = TimeControl::Absolute { main: seconds(60) };
+        assert_eq!(play(control, &[20, 30]).unwrap().main, seconds(10));
+        assert_eq!(play(control, &[20, 40]).unwrap().main, Duration::ZERO);
+        assert_eq!(play(control, &[20, 41]), None);
+
+        let source = Arc::new(MockTime::default());
+        let clock = Clock::new(control, source.clone());
+        source.advance(seconds(15));
+        assert_eq!(clock.elapsed(), seconds(15));
+        assert_eq!(clock.time_for_move(Stone::Black), seconds(45));
+        // the clock does not move until the move is played
+        assert_eq!(clock.black.main, seconds(60));
+    }
+
+    #[test]
+    fn clock_byo_yomi() {
+        let control = TimeControl::ByoYomi {
+            main: seconds(60),
+            period: seconds(10),
+            periods: 3,
+        };
+        // moves within a period keep the periods
+        let time = play(control, &[55, 10, 10, 10]).unwrap();
+        assert_eq!(time.main, Duration::ZERO);
+        assert_eq!(time.periods, 3);
+        // 10s of main time is not enough, and a period is overrun
+        assert_eq!(play(control, &[50, 25]).unwrap().periods, 2);
+        assert_eq!(play(control, &[60, 25]).unwrap().periods, 1);
+        assert_eq!(play(control, &[60, 30]).unwrap().periods, 1);
+        assert_eq!(play(control, &[60, 31]), None);
+    }
+
+    #[test]
+    fn clock_canadian() {
+        let control = TimeControl::Canadian {
+            main: seconds(60),
+            period: seconds(30),
+            stones: 3,
+        };
+        let time = play(control, &[60, 10, 10]).unwrap();
+        assert_eq!(time.period, seconds(10));
+        assert_eq!(time.stones, 1);
+        // the period is reset after 3 stones
+        let time = play(control, &[60, 10, 10, 10]).unwrap();
+        assert_eq!(time.period, seconds(30));
+        assert_eq!(time.stones, 3);
+        assert_eq!(play(control, &[60, 10, 10, 11]), None);
+        // the main time overrun is taken from the first period
+        assert_eq!(play(control, &[70]).unwrap().period, seconds(20));
+
+        // no stones to play in a period is absolute time
+        let control = TimeControl::Canadian {
+            main: seconds(60),
+            period: seconds(30),
+            stones: 0,
+        };
+        let clock = Clock::new(control, Arc::new(MockTime::default()));
+        assert_eq!(clock.control, TimeControl::Absolute { main: seconds(60) });
+        assert_eq!(play(control, &[50]).unwrap().main, seconds(10));
+        assert_eq!(play(control, &[50, 11]), None);
+    }
+}
@@ -1,19 +1,43 @@
+use std::fmt;
 use std::ops::Add;
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum FracError {
+    ZeroDenominator,
+}
+
+impl fmt::Display for FracError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FracError::ZeroDenominator => write!(f, "denominator must not be zero."),
+        }
+    }
+}
+
+impl std::error::Error for FracError {}
+
 #[derive(Debug, PartialEq)]
-struct Frac {
+pub struct Frac {
     num: usize,
     denom: usize,
 }
 
 impl Frac {
-    pub fn new(num: usize, denom: usize) -> Result<Self, String> {
+    pub fn new(num: usize, denom: usize) -> Result<Self, FracError> {
         if denom == 0 {
-            Err("denominator must not be zero.".to_string())
+            Err(FracError::ZeroDenominator)
         } else {
             Ok(Frac { num, denom })
         }
     }
+
+    pub fn num(&self) -> usize {
+        self.num
+    }
+
+    pub fn denom(&self) -> usize {
+        self.denom
+    }
 }
 
 impl Add for Frac {
@@ -27,7 +51,7 @@ impl Add for Frac {
             denom: new_denom,
         };
         for i in (2..=new_frac.denom).rev() {
-            if new_frac.num % i == 0 && new_frac.denom % i == 0 {
+            if new_frac.num.is_multiple_of(i) && new_frac.denom.is_multiple_of(i) {
                 return Frac {
                     num: new_frac.num / i,
                     denom: new_frac.denom / i,
@@ -45,7 +69,7 @@ mod tests {
     #[test]
     fn test_frac() {
         let a = Frac::new(1, 0);
-        assert!(a.is_err());
+        assert_eq!(a, Err(FracError::ZeroDenominator));
 
         let a = Frac::new(1, 1).unwrap();
         let b = Frac::new(1, 1).unwrap();
@@ -66,5 +90,6 @@ mod tests {
         let b = Frac::new(1, 4).unwrap();
         let c = a + b;
         assert_eq!(c, Frac::new(1, 1).unwrap());
+        assert_eq!((c.num(), c.denom()), (1, 1));
     }
 }
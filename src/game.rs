@@ -1,128 +1,1249 @@
-use crate::board::{BOARD_SIZE, Board, BoardCell, Point, Stone};
+use crate::board::{Board, BoardError, Point, Stone};
+use crate::clock::Clock;
+use crate::rules::Ruleset;
+use crate::score::{self, Score, Scoring};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
 
 #[derive(Debug, Clone)]
 pub struct Game {
     pub turn: Stone,
     pub board: Board,
     pub status: GameStatus,
-    pub komi: f32,
+    // set by set_ruleset, which passes the ko rule and suicide on to the board
+    ruleset: Ruleset,
+    pub info: GameInfo,
+    // stones placed before the first move, e.g. SGF AB/AW
+    pub setup: Vec<(Stone, Point)>,
+    // every command played so far with the player who played it
+    pub moves: Vec<(Stone, Command)>,
+    // commands taken back by undo, the last undone at the end
+    undone_moves: Vec<(Stone, Command)>,
+    // number of handicap stones, recorded as SGF HA
+    pub handicap: usize,
+    // handicap stones black has still to place by free placement
+    free_handicap_left: usize,
     pass_count: u8,
+    // number of moves when the players resumed after passes, the last at the end
+    resumed_at: Vec<usize>,
+    // stones marked dead in the scoring phase, removed when the game is counted
+    dead_stones: HashSet<Point>,
+    // the player who accepted the marked dead stones, waiting for the opponent
+    agreed: Option<Stone>,
+    // no time limit without a clock
+    pub clock: Option<Clock>,
+}
+
+// komi of handicap games, white wins ties instead of komi
+const HANDICAP_KOMI: f32 = 0.5;
+
+// record of the game which does not affect the play
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GameInfo {
+    pub black_player: Option<String>,
+    pub white_player: Option<String>,
+    // set when the game ends, written as SGF RE. e.g. "B+3.5", "W+R", "0"
+    pub result: Option<GameResult>,
 }
 
 impl Game {
-    pub fn new() -> Game {
-        Game {
+    pub fn new(size: usize, ruleset: Ruleset) -> Game {
+        let mut game = Game {
             turn: Stone::Black,
-            board: Board::new(),
+            board: Board::new(size),
             status: GameStatus::Continue,
-            komi: 3.75,
+            ruleset,
+            info: GameInfo::default(),
+            setup: vec![],
+            moves: vec![],
+            undone_moves: vec![],
+            handicap: 0,
+            free_handicap_left: 0,
             pass_count: 0,
+            resumed_at: vec![],
+            dead_stones: HashSet::new(),
+            agreed: None,
+            clock: None,
+        };
+        game.set_ruleset(ruleset);
+        game
+    }
+
+    pub fn ruleset(&self) -> &Ruleset {
+        &self.ruleset
+    }
+
+    // the board checks the moves by the ko rule and suicide of the ruleset
+    pub fn set_ruleset(&mut self, ruleset: Ruleset) {
+        self.ruleset = ruleset;
+        self.board.ko_rule = ruleset.ko_rule;
+        self.board.suicide_allowed = ruleset.suicide_allowed;
+    }
+
+    // place handicap stones on the star points. white moves first then.
+    pub fn set_fixed_handicap(&mut self, handicap: usize) -> Result<Vec<Point>, GameError> {
+        let points = self.board.handicap_points(handicap)?;
+        self.set_free_handicap(&points)?;
+        Ok(points)
+    }
+
+    // place handicap stones on any points. white moves first then.
+    pub fn set_free_handicap(&mut self, points: &[Point]) -> Result<(), GameError> {
+        self.check_empty_board()?;
+        let distinct_points = points.iter().collect::<HashSet<_>>();
+        if distinct_points.len() != points.len()
+            || points.iter().any(|&p| !self.board.get(p).is_empty())
+        {
+            return Err(GameError::InvalidHandicapStones(points.to_vec()));
         }
+        self.start_free_handicap(points.len())?;
+        for &point in points {
+            self.play(Command::Move {
+                stone: Stone::Black,
+                point,
+            })?;
+        }
+        Ok(())
     }
 
-    pub fn play(&mut self, command: Command) -> Result<(), String> {
-        match command {
-            Command::Move { stone, point } => match self.board.put(stone, point) {
-                Ok(_) => {
-                    self.flip_turn();
-                    // game continues as long as someone puts stone
-                    self.pass_count = 0;
-                    Ok(())
+    // black places the handicap stones by the next moves, then white moves first.
+    pub fn start_free_handicap(&mut self, handicap: usize) -> Result<(), GameError> {
+        self.check_empty_board()?;
+        if handicap < 2 || handicap > self.board.size() * self.board.size() - 1 {
+            return Err(GameError::InvalidHandicap(handicap));
+        }
+        self.handicap = handicap;
+        self.free_handicap_left = handicap;
+        self.ruleset.komi = HANDICAP_KOMI;
+        self.turn = Stone::Black;
+        Ok(())
+    }
+
+    fn check_empty_board(&self) -> Result<(), GameError> {
+        if !self.moves.is_empty() || !self.setup.is_empty() {
+            return Err(GameError::BoardNotEmpty);
+        }
+        Ok(())
+    }
+
+    pub fn free_handicap_left(&self) -> usize {
+        self.free_handicap_left
+    }
+
+    pub fn add_setup_stone(&mut self, stone: Stone, point: Point) -> Result<(), GameError> {
+        if !self.moves.is_empty() {
+            return Err(GameError::SetupAfterMoves);
+        }
+        self.board.setup(stone, point)?;
+        self.setup.push((stone, point));
+        Ok(())
+    }
+
+    pub fn play(&mut self, command: Command) -> Result<(), GameError> {
+        self.check_playing()?;
+        if self.free_handicap_left > 0 {
+            return self.place_handicap_stone(command);
+        }
+        if let Command::Move { stone, .. } = command
+            && stone != self.turn
+        {
+            return Err(GameError::WrongTurn(stone));
+        }
+        let player = self.turn;
+        let time = match &self.clock {
+            Some(clock) => match clock.charge(player) {
+                Some(time) => Some(time),
+                None => {
+                    self.forfeit(player, EndReason::Time);
+                    return Err(GameError::TimeOver(player));
                 }
-                Err(err) => Err(format!("failed to execute command: {}", err)),
             },
+            None => None,
+        };
+        self.play_command(command)?;
+        if let (Some(clock), Some(time)) = (self.clock.as_mut(), time) {
+            clock.punch(player, time);
+        }
+        // a new move makes another line of the game
+        self.undone_moves.clear();
+        Ok(())
+    }
+
+    // play regardless of the turn, e.g. consecutive moves of the same color
+    // by GTP play or in SGF records. the opponent of the player moves next
+    pub fn play_as(&mut self, player: Stone, command: Command) -> Result<(), GameError> {
+        if let Command::Move { stone, .. } = command
+            && stone != player
+        {
+            return Err(GameError::WrongTurn(stone));
+        }
+        let turn = self.turn;
+        let status = self.status.clone();
+        self.turn = player;
+        let result = self.play(command);
+        // the player stays to move when the command ended the game, e.g. by time
+        if result.is_err() && self.status == status {
+            self.turn = turn;
+        }
+        result
+    }
+
+    fn check_playing(&self) -> Result<(), GameError> {
+        match self.status {
+            GameStatus::Continue => Ok(()),
+            GameStatus::Scoring => Err(GameError::Scoring),
+            GameStatus::End => Err(GameError::GameOver),
+        }
+    }
+
+    // every command the player to move can play, including pass but not resign
+    pub fn legal_commands(&self) -> Vec<Command> {
+        if self.status != GameStatus::Continue {
+            return vec![];
+        }
+        let points = if self.free_handicap_left > 0 {
+            // handicap stones can be placed on any empty point
+            self.board
+                .points()
+                .filter(|&point| self.board.get(point).is_empty())
+                .collect()
+        } else {
+            self.board.find_available_points(self.turn)
+        };
+        let mut commands = points
+            .into_iter()
+            .map(|point| Command::Move {
+                stone: self.turn,
+                point,
+            })
+            .collect::<Vec<_>>();
+        if self.free_handicap_left == 0 {
+            commands.push(Command::Pass);
+        }
+        commands
+    }
+
+    fn play_command(&mut self, command: Command) -> Result<(), GameError> {
+        match command {
+            Command::Move { stone, point } => {
+                self.board.put(stone, point)?;
+                self.moves.push((stone, command));
+                self.flip_turn();
+                // game continues as long as someone puts stone
+                self.pass_count = 0;
+                Ok(())
+            }
             Command::Pass => {
+                self.board.pass(self.turn);
+                self.moves.push((self.turn, command));
                 self.flip_turn();
                 self.pass_count += 1;
-                // double pass means game set
+                // double pass means game set, after the dead stones are agreed
                 if self.pass_count == 2 {
-                    self.status = GameStatus::End;
+                    self.start_scoring();
                 }
                 Ok(())
             }
+            Command::Resign => {
+                let winner = self.turn.flip();
+                self.moves.push((self.turn, command));
+                self.end(GameResult::by(winner, EndReason::Resign));
+                Ok(())
+            }
+        }
+    }
+
+    // the loser runs out of time or breaks the rules, e.g. plays an illegal move
+    pub fn forfeit(&mut self, loser: Stone, reason: EndReason) {
+        self.end(GameResult::by(loser.flip(), reason));
+    }
+
+    fn end(&mut self, result: GameResult) {
+        self.status = GameStatus::End;
+        self.info.result = Some(result);
+    }
+
+    // take back the last command, restoring the board, turn, passes and status.
+    // the clocks are not turned back
+    pub fn undo(&mut self) -> Result<(), GameError> {
+        // a forfeit is not a command in the moves, so undo would take back a move before it
+        if let Some(GameResult {
+            reason: EndReason::Time | EndReason::Forfeit,
+            ..
+        }) = self.info.result
+        {
+            return Err(GameError::GameOver);
+        }
+        let (stone, command) = self.moves.pop().ok_or(GameError::NoMoveToUndo)?;
+        // resign is not on the board
+        if command != Command::Resign {
+            self.board.undo()?;
+        }
+        self.turn = stone;
+        // a resume is taken back together with the last command before it
+        if self.resumed_at.last() > Some(&self.moves.len()) {
+            self.resumed_at.pop();
         }
+        // passes in a row before the command, since the last resume
+        let since_resume = self.moves.len() - self.resumed_at.last().copied().unwrap_or(0);
+        self.pass_count = self
+            .moves
+            .iter()
+            .rev()
+            .take(since_resume)
+            .take_while(|(_, command)| *command == Command::Pass)
+            .count() as u8;
+        if self.pass_count >= 2 {
+            self.start_scoring();
+        } else {
+            self.status = GameStatus::Continue;
+            self.info.result = None;
+            self.dead_stones.clear();
+            self.agreed = None;
+        }
+        self.undone_moves.push((stone, command));
+        Ok(())
+    }
+
+    // play the last command taken back by undo again
+    pub fn redo(&mut self) -> Result<(), GameError> {
+        self.check_playing()?;
+        let (stone, command) = self.undone_moves.pop().ok_or(GameError::NoMoveToRedo)?;
+        self.turn = stone;
+        self.play_command(command)
+    }
+
+    fn place_handicap_stone(&mut self, command: Command) -> Result<(), GameError> {
+        let point = match command {
+            Command::Move {
+                stone: Stone::Black,
+                point,
+            } => point,
+            Command::Move { stone, .. } => return Err(GameError::WrongTurn(stone)),
+            _ => return Err(GameError::HandicapLeft(self.free_handicap_left)),
+        };
+        self.add_setup_stone(Stone::Black, point)?;
+        self.free_handicap_left -= 1;
+        if self.free_handicap_left == 0 {
+            self.turn = Stone::White;
+        }
+        Ok(())
     }
 
     pub fn flip_turn(&mut self) {
         self.turn = self.turn.flip();
     }
 
-    pub fn game_end(&mut self) -> GameResult {
-        // based on chinese rule
-        let mut black = 0.0;
-        let mut white = 0.0;
-        for row in 1..=BOARD_SIZE as i8 {
-            for col in 1..=BOARD_SIZE as i8 {
-                match self.board.get(Point { row, col }) {
-                    BoardCell::Space(Some(Stone::Black)) => {
-                        black += 1.0;
-                    }
-                    BoardCell::Space(Some(Stone::White)) => {
-                        white += 1.0;
-                    }
-                    _ => {
-                        continue;
-                    }
-                }
-            }
+    // how long the player to move can think on this move. None without a clock
+    pub fn time_for_move(&self) -> Option<Duration> {
+        self.clock
+            .as_ref()
+            .map(|clock| clock.time_for_move(self.turn))
+    }
+
+    // every stone is alive until marked dead
+    fn start_scoring(&mut self) {
+        self.status = GameStatus::Scoring;
+        self.info.result = None;
+        self.dead_stones.clear();
+        self.agreed = None;
+    }
+
+    pub fn dead_stones(&self) -> &HashSet<Point> {
+        &self.dead_stones
+    }
+
+    pub fn is_dead(&self, point: Point) -> bool {
+        self.dead_stones.contains(&point)
+    }
+
+    // mark the whole group at the point dead or alive in the scoring phase.
+    // any change has to be accepted again by both players
+    pub fn set_dead(&mut self, point: Point, dead: bool) -> Result<(), GameError> {
+        if self.status != GameStatus::Scoring {
+            return Err(GameError::NotScoring);
         }
-        white += self.komi;
-        println!("black stones: {}", black);
-        println!("white stones: {}", white);
-        println!("black prisoners: {:?}", self.board.black_prisoners);
-        println!("white prisoners: {:?}", self.board.white_prisoners);
-        GameResult {
-            black,
-            white,
-            winner: if black > white {
-                Winner::Black
-            } else if black < white {
-                Winner::White
+        let group = self.board.group_at(point);
+        if group.is_empty() {
+            return Err(GameError::NoStone(point));
+        }
+        for stone in group {
+            if dead {
+                self.dead_stones.insert(stone);
             } else {
-                Winner::Draw
-            },
+                self.dead_stones.remove(&stone);
+            }
+        }
+        self.agreed = None;
+        Ok(())
+    }
+
+    pub fn toggle_dead(&mut self, point: Point) -> Result<(), GameError> {
+        self.set_dead(point, !self.is_dead(point))
+    }
+
+    // the player accepts the marked dead stones. the game ends with the count
+    // when the opponent has accepted them too
+    pub fn agree(&mut self, player: Stone) -> Result<(), GameError> {
+        if self.status != GameStatus::Scoring {
+            return Err(GameError::NotScoring);
         }
+        match self.agreed {
+            Some(stone) if stone != player => self.end(self.game_end()),
+            _ => self.agreed = Some(player),
+        }
+        Ok(())
+    }
+
+    // the players disagree about the dead stones and play on to settle them
+    pub fn resume(&mut self) -> Result<(), GameError> {
+        if self.status != GameStatus::Scoring {
+            return Err(GameError::NotScoring);
+        }
+        self.status = GameStatus::Continue;
+        self.pass_count = 0;
+        self.resumed_at.push(self.moves.len());
+        self.dead_stones.clear();
+        self.agreed = None;
+        Ok(())
+    }
+
+    // the result by counting the board without the stones marked dead
+    pub fn game_end(&self) -> GameResult {
+        self.score(self.ruleset.scoring, &self.dead_stones)
+    }
+
+    // score the game by any scoring, regardless of the scoring of the game
+    pub fn score(&self, scoring: Scoring, dead_stones: &HashSet<Point>) -> GameResult {
+        let (black, white) = self.count(scoring, dead_stones);
+        GameResult::by_score(&black, &white)
+    }
+
+    // scores of black and white
+    pub fn count(&self, scoring: Scoring, dead_stones: &HashSet<Point>) -> (Score, Score) {
+        score::score(&self.board, scoring, self.ruleset.komi, dead_stones)
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Command {
     Move { stone: Stone, point: Point },
     Pass,
+    // the player to move gives up the game
+    Resign,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum GameStatus {
     Continue,
+    // both players passed. they mark dead stones and agree on them, or resume the game
+    Scoring,
     End,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameError {
+    // the move is illegal on the board
+    Board(BoardError),
+    // the stone is not of the player to move
+    WrongTurn(Stone),
+    GameOver,
+    // no move is played while the dead stones are marked
+    Scoring,
+    // dead stones are marked only after both players passed
+    NotScoring,
+    // no group to mark dead at the point
+    NoStone(Point),
+    // the player to move ran out of time
+    TimeOver(Stone),
+    NoMoveToUndo,
+    NoMoveToRedo,
+    SetupAfterMoves,
+    BoardNotEmpty,
+    InvalidHandicap(usize),
+    InvalidHandicapStones(Vec<Point>),
+    // black has to place this many handicap stones before any other move
+    HandicapLeft(usize),
+}
+
+impl From<BoardError> for GameError {
+    fn from(err: BoardError) -> Self {
+        GameError::Board(err)
+    }
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameError::Board(err) => write!(f, "{}", err),
+            GameError::WrongTurn(stone) => write!(f, "it is not the turn of {:?}", stone),
+            GameError::GameOver => write!(f, "the game is over"),
+            GameError::Scoring => write!(f, "the dead stones are being marked"),
+            GameError::NotScoring => write!(f, "the game is not in the scoring phase"),
+            GameError::NoStone(point) => write!(f, "no stone on the point: {:?}", point),
+            GameError::TimeOver(stone) => write!(f, "{:?} ran out of time", stone),
+            GameError::NoMoveToUndo => write!(f, "no move to undo"),
+            GameError::NoMoveToRedo => write!(f, "no move to redo"),
+            GameError::SetupAfterMoves => {
+                write!(f, "setup stones must be placed before the first move")
+            }
+            GameError::BoardNotEmpty => {
+                write!(f, "handicap stones must be placed on the empty board")
+            }
+            GameError::InvalidHandicap(handicap) => write!(f, "invalid handicap: {}", handicap),
+            GameError::InvalidHandicapStones(points) => {
+                write!(f, "invalid handicap stones: {:?}", points)
+            }
+            GameError::HandicapLeft(handicap) => {
+                write!(f, "black has to place {} more handicap stones", handicap)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GameError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GameError::Board(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct GameResult {
-    black: f32,
-    white: f32,
-    winner: Winner,
+    pub winner: Winner,
+    pub reason: EndReason,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EndReason {
+    // by counting, with the breakdown of both players
+    Score { black: Score, white: Score },
+    // by counting, as recorded in SGF, which keeps only the points the winner wins by
+    Margin(f32),
+    Resign,
+    // the loser ran out of time
+    Time,
+    // the loser broke the rules, e.g. played an illegal move
+    Forfeit,
+}
+
+impl GameResult {
+    pub fn by_score(black: &Score, white: &Score) -> GameResult {
+        let winner = if black.total() > white.total() {
+            Winner::Black
+        } else if black.total() < white.total() {
+            Winner::White
+        } else {
+            Winner::Draw
+        };
+        GameResult {
+            winner,
+            reason: EndReason::Score {
+                black: *black,
+                white: *white,
+            },
+        }
+    }
+
+    pub fn by(winner: Stone, reason: EndReason) -> GameResult {
+        GameResult {
+            winner: match winner {
+                Stone::Black => Winner::Black,
+                Stone::White => Winner::White,
+            },
+            reason,
+        }
+    }
+
+    // how many points the winner wins by. None unless decided by counting
+    pub fn margin(&self) -> Option<f32> {
+        match self.reason {
+            EndReason::Score { black, white } => Some((black.total() - white.total()).abs()),
+            EndReason::Margin(margin) => Some(margin),
+            _ => None,
+        }
+    }
+
+    pub fn winner_stone(&self) -> Option<Stone> {
+        match self.winner {
+            Winner::Black => Some(Stone::Black),
+            Winner::White => Some(Stone::White),
+            Winner::Draw => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Winner {
     Black,
     White,
     Draw,
 }
 
+// formatted as SGF RE property. e.g. "B+3.5", "W+R", "B+T", "B+F", "0"
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let winner = match self.winner {
+            Winner::Black => "B",
+            Winner::White => "W",
+            Winner::Draw => return write!(f, "0"),
+        };
+        match self.reason {
+            EndReason::Score { .. } | EndReason::Margin(_) => {
+                write!(f, "{}+{}", winner, self.margin().unwrap_or_default())
+            }
+            EndReason::Resign => write!(f, "{}+R", winner),
+            EndReason::Time => write!(f, "{}+T", winner),
+            EndReason::Forfeit => write!(f, "{}+F", winner),
+        }
+    }
+}
+
+// parses SGF RE property. the long forms like "B+Resign" are accepted too
+impl FromStr for GameResult {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s == "0" || s.eq_ignore_ascii_case("draw") {
+            return Ok(GameResult {
+                winner: Winner::Draw,
+                reason: EndReason::Margin(0.0),
+            });
+        }
+        let invalid = || format!("invalid result: {}", s);
+        let (winner, reason) = s.split_once('+').ok_or_else(invalid)?;
+        let winner = match winner {
+            "B" => Stone::Black,
+            "W" => Stone::White,
+            _ => return Err(invalid()),
+        };
+        let reason = match reason {
+            "R" | "Resign" => EndReason::Resign,
+            "T" | "Time" => EndReason::Time,
+            "F" | "Forfeit" => EndReason::Forfeit,
+            margin => EndReason::Margin(margin.parse::<f32>().map_err(|_| invalid())?),
+        };
+        Ok(GameResult::by(winner, reason))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::{MockTime, TimeControl};
+    use std::sync::Arc;
 
     #[test]
     fn game_flip_turn() {
-        let mut game = Game::new();
-        let previous_stone = game.turn.clone();
+        let mut game = Game::new(9, Ruleset::default());
+        let previous_stone = game.turn;
         game.flip_turn();
-        let new_stone = game.turn.clone();
+        let new_stone = game.turn;
 
         assert_eq!(previous_stone, Stone::Black);
         assert_eq!(new_stone, Stone::White);
     }
+
+    #[test]
+    fn game_end_respects_board_size() {
+        // ┌───────
+        // │   ① ②
+        // │ ① ○ ┐
+        // │ ② └─●
+        let mut game = Game::new(2, Ruleset::default());
+        game.ruleset.komi = 0.5;
+        game.play(Command::Move {
+            stone: Stone::Black,
+            point: Point { row: 1, col: 1 },
+        })
+        .unwrap();
+        game.play(Command::Move {
+            stone: Stone::White,
+            point: Point { row: 2, col: 2 },
+        })
+        .unwrap();
+        let (black, white) = game.count(Scoring::Area, &HashSet::new());
+        assert_eq!(black.total(), 1.0);
+        assert_eq!(white.total(), 1.5);
+        assert_eq!(game.game_end().to_string(), "W+0.5");
+
+        let mut game = Game::new(19, Ruleset::default());
+        game.play(Command::Move {
+            stone: Stone::Black,
+            point: Point { row: 19, col: 19 },
+        })
+        .unwrap();
+        let (black, _) = game.count(Scoring::Area, &HashSet::new());
+        assert_eq!(black.stones, 1);
+        assert_eq!(black.territory, 19 * 19 - 1);
+    }
+
+    #[test]
+    fn game_scoring() {
+        // black captured a white stone at ①-①, and the black stone at ②-④ may be dead.
+        // ┌───────────┐
+        // │   ① ② ③ ④ │
+        // │ ① ┌─○ ● ┐ │
+        // │ ② ○ ○ ● ○ │
+        // │ ③ ├─○ ● ┤ │
+        // │ ④ └─○ ● ┘ │
+        let mut game = Game::new(4, Ruleset::chinese());
+        game.ruleset.komi = 0.5;
+        for (stone, row, col) in [
+            (Stone::Black, 1, 2),
+            (Stone::White, 1, 1),
+            (Stone::Black, 2, 1),
+            (Stone::White, 1, 3),
+            (Stone::Black, 2, 2),
+            (Stone::White, 2, 3),
+            (Stone::Black, 3, 2),
+            (Stone::White, 3, 3),
+            (Stone::Black, 4, 2),
+            (Stone::White, 4, 3),
+            (Stone::Black, 2, 4),
+        ] {
+            game.play(Command::Move {
+                stone,
+                point: Point { row, col },
+            })
+            .unwrap();
+        }
+        game.play(Command::Pass).unwrap();
+        game.play(Command::Pass).unwrap();
+        assert_eq!(game.status, GameStatus::Scoring);
+        game.agree(Stone::Black).unwrap();
+        game.agree(Stone::White).unwrap();
+        assert_eq!(game.status, GameStatus::End);
+        assert_eq!(game.board.black_prisoners, 1);
+
+        // area: black 6 stones + 3 territory, white 4 stones + 0.5
+        let result = game.info.result.unwrap();
+        assert_eq!(result.to_string(), "B+4.5");
+        let EndReason::Score { black, white } = result.reason else {
+            panic!("not decided by counting: {:?}", result.reason);
+        };
+        assert_eq!((black.stones, black.territory), (6, 3));
+        assert_eq!((white.stones, white.komi), (4, 0.5));
+
+        // territory: black 3 territory + 1 prisoner, white 0.5
+        game.ruleset.scoring = Scoring::Territory;
+        let (black, _) = game.count(Scoring::Territory, &HashSet::new());
+        assert_eq!(black.total(), 4.0);
+        assert_eq!(game.game_end().to_string(), "B+3.5");
+
+        // the same game when the black stone at ②-④ is dead
+        let dead_stones = HashSet::from([Point { row: 2, col: 4 }]);
+        let (black, white) = game.count(Scoring::Territory, &dead_stones);
+        assert_eq!(black.total(), 4.0);
+        assert_eq!(white.total(), 5.5);
+        let result = game.score(Scoring::Territory, &dead_stones);
+        assert_eq!(result.to_string(), "W+1.5");
+        assert_eq!(result.margin(), Some(1.5));
+        let (black, white) = game.count(Scoring::Area, &dead_stones);
+        assert_eq!(black.total(), 8.0);
+        assert_eq!(white.total(), 8.5);
+    }
+
+    #[test]
+    fn game_dead_stone_agreement() {
+        // the white stones at ④-④ and ④-⑤ are dead in the territory of black
+        // ┌─────────────┐
+        // │   ① ② ③ ④ ⑤ │
+        // │ ① ┌─┬─○ ┬─┐ │
+        // │ ② ├─┼─○ ┼─┤ │
+        // │ ③ ├─┼─○ ○ ○ │
+        // │ ④ ├─┼─┼─● ● │
+        // │ ⑤ └─┴─┴─┴─┘ │
+        let mut game = Game::new(5, Ruleset::chinese());
+        game.ruleset.komi = 0.5;
+        let stone = |row, col| Point { row, col };
+        let put = |stone, point| Command::Move { stone, point };
+        for command in [
+            put(Stone::Black, stone(1, 3)),
+            put(Stone::White, stone(4, 4)),
+            put(Stone::Black, stone(2, 3)),
+            put(Stone::White, stone(4, 5)),
+            put(Stone::Black, stone(3, 3)),
+            Command::Pass,
+            put(Stone::Black, stone(3, 4)),
+            Command::Pass,
+            put(Stone::Black, stone(3, 5)),
+        ] {
+            game.play(command).unwrap();
+        }
+        assert_eq!(game.set_dead(stone(4, 4), true), Err(GameError::NotScoring));
+        game.play(Command::Pass).unwrap();
+        game.play(Command::Pass).unwrap();
+        assert_eq!(game.status, GameStatus::Scoring);
+        assert_eq!(game.info.result, None);
+        assert_eq!(game.play(Command::Pass), Err(GameError::Scoring));
+        assert_eq!(
+            game.toggle_dead(stone(5, 5)),
+            Err(GameError::NoStone(stone(5, 5)))
+        );
+
+        // marking a stone marks its whole group
+        game.toggle_dead(stone(4, 5)).unwrap();
+        assert!(game.is_dead(stone(4, 4)));
+        assert_eq!(game.dead_stones().len(), 2);
+        // black: 5 stones + 20 points, white: 0.5
+        assert_eq!(game.game_end().to_string(), "B+24.5");
+
+        // a change of the marks cancels the agreement
+        game.agree(Stone::White).unwrap();
+        game.toggle_dead(stone(1, 3)).unwrap();
+        game.agree(Stone::Black).unwrap();
+        assert_eq!(game.status, GameStatus::Scoring);
+        game.toggle_dead(stone(1, 3)).unwrap();
+        game.agree(Stone::Black).unwrap();
+        game.agree(Stone::Black).unwrap();
+        assert_eq!(game.status, GameStatus::Scoring);
+        game.agree(Stone::White).unwrap();
+        assert_eq!(game.status, GameStatus::End);
+        assert_eq!(game.info.result, Some(game.game_end()));
+        assert_eq!(game.game_end().to_string(), "B+24.5");
+        assert_eq!(game.agree(Stone::White), Err(GameError::NotScoring));
+
+        // taking back the last pass clears the marks
+        game.undo().unwrap();
+        assert_eq!(game.status, GameStatus::Continue);
+        assert!(game.dead_stones().is_empty());
+
+        // white disagrees and plays on
+        game.play(Command::Pass).unwrap();
+        game.toggle_dead(stone(4, 4)).unwrap();
+        game.resume().unwrap();
+        assert_eq!(game.status, GameStatus::Continue);
+        assert!(!game.is_dead(stone(4, 4)));
+        assert_eq!(game.turn, Stone::White);
+        game.play(Command::Move {
+            stone: Stone::White,
+            point: stone(5, 3),
+        })
+        .unwrap();
+        assert_eq!(game.resume(), Err(GameError::NotScoring));
+    }
+
+    #[test]
+    fn game_under_each_ruleset() {
+        // white's group at the corner has only ①-① left.
+        // ┌───────────
+        // │   ① ② ③
+        // │ ① ┌─● ○
+        // │ ② ● ● ○
+        // │ ③ ○ ○ ┼─
+        for (name, ruleset) in Ruleset::presets() {
+            let mut game = Game::new(9, ruleset);
+            for (stone, row, col) in [
+                (Stone::Black, 1, 3),
+                (Stone::White, 1, 2),
+                (Stone::Black, 2, 3),
+                (Stone::White, 2, 1),
+                (Stone::Black, 3, 1),
+                (Stone::White, 2, 2),
+                (Stone::Black, 3, 2),
+            ] {
+                game.play(Command::Move {
+                    stone,
+                    point: Point { row, col },
+                })
+                .unwrap();
+            }
+            let suicide = game.play(Command::Move {
+                stone: Stone::White,
+                point: Point { row: 1, col: 1 },
+            });
+            assert_eq!(suicide.is_ok(), ruleset.suicide_allowed, "{}", name);
+            if suicide.is_ok() {
+                assert_eq!(game.board.black_prisoners, 4, "{}", name);
+            }
+
+            let (black, white) = game.count(ruleset.scoring, &HashSet::new());
+            match ruleset.scoring {
+                Scoring::Area => assert_eq!(black.stones, 4, "{}", name),
+                Scoring::Territory => assert_eq!(black.stones, 0, "{}", name),
+            }
+            assert_eq!(white.komi, ruleset.komi, "{}", name);
+        }
+    }
+
+    #[test]
+    fn game_set_ruleset() {
+        // the same position as above, the ruleset changes before the suicide at ①-①
+        let mut game = Game::new(9, Ruleset::japanese());
+        for (stone, row, col) in [
+            (Stone::Black, 1, 3),
+            (Stone::White, 1, 2),
+            (Stone::Black, 2, 3),
+            (Stone::White, 2, 1),
+            (Stone::Black, 3, 1),
+            (Stone::White, 2, 2),
+            (Stone::Black, 3, 2),
+        ] {
+            game.play(Command::Move {
+                stone,
+                point: Point { row, col },
+            })
+            .unwrap();
+        }
+        let suicide = Command::Move {
+            stone: Stone::White,
+            point: Point { row: 1, col: 1 },
+        };
+        assert_eq!(
+            game.play(suicide),
+            Err(GameError::Board(BoardError::Suicide(Point {
+                row: 1,
+                col: 1
+            })))
+        );
+
+        game.set_ruleset(Ruleset::new_zealand());
+        assert_eq!(game.ruleset(), &Ruleset::new_zealand());
+        assert_eq!(game.board.ko_rule, Ruleset::new_zealand().ko_rule);
+        game.play(suicide).unwrap();
+        assert_eq!(game.board.black_prisoners, 4);
+    }
+
+    #[test]
+    fn game_tromp_taylor() {
+        // white fills ①-③ and removes its own stones.
+        // then a single stone suicide at ①-① would repeat the position.
+        // ┌─────────┐   ┌─────────┐
+        // │   ① ② ③ │   │   ① ② ③ │
+        // │ ① ● ● ┐ │   │ ① ┌─○ ┐ │
+        // │ ② ○ ○ ○ │   │ ② ○ ○ ○ │
+        // │ ③ └─┴─┘ │   │ ③ └─┴─┘ │
+        let mut game = Game::new(3, Ruleset::tromp_taylor());
+        game.ruleset.komi = 0.5;
+        for (stone, row, col) in [
+            (Stone::Black, 2, 1),
+            (Stone::White, 1, 1),
+            (Stone::Black, 2, 2),
+            (Stone::White, 1, 2),
+            (Stone::Black, 2, 3),
+        ] {
+            game.play(Command::Move {
+                stone,
+                point: Point { row, col },
+            })
+            .unwrap();
+        }
+        // ①-③ is suicide of three stones
+        game.play(Command::Move {
+            stone: Stone::White,
+            point: Point { row: 1, col: 3 },
+        })
+        .unwrap();
+        assert_eq!(game.board.black_prisoners, 3);
+        assert_eq!(game.board.white_prisoners, 0);
+
+        // positional superko forbids the suicide of a single stone
+        let corner = Point { row: 1, col: 1 };
+        game.play(Command::Move {
+            stone: Stone::Black,
+            point: Point { row: 1, col: 2 },
+        })
+        .unwrap();
+        assert_eq!(
+            game.play(Command::Move {
+                stone: Stone::White,
+                point: corner,
+            }),
+            Err(GameError::Board(BoardError::Superko(corner)))
+        );
+        game.play(Command::Pass).unwrap();
+        game.play(Command::Pass).unwrap();
+
+        // pure area: 4 stones + 5 empty points reaching only black, without the prisoners
+        let (black, _) = game.count(Scoring::Area, &HashSet::new());
+        assert_eq!(black.stones, 4);
+        assert_eq!(black.total(), 9.0);
+        assert_eq!(black.prisoners, 0);
+        assert_eq!(game.game_end().to_string(), "B+8.5");
+    }
+
+    #[test]
+    fn game_handicap() {
+        let mut game = Game::new(19, Ruleset::japanese());
+        let points = game.set_fixed_handicap(4).unwrap();
+        assert_eq!(game.handicap, 4);
+        assert_eq!(game.setup.len(), 4);
+        assert!(points.iter().all(|&p| game.board.get(p).is_stone()));
+        assert_eq!(game.turn, Stone::White);
+        assert_eq!(game.ruleset.komi, 0.5);
+        assert!(game.moves.is_empty());
+        // only once, before the first move
+        assert_eq!(game.set_fixed_handicap(2), Err(GameError::BoardNotEmpty));
+        game.play(Command::Pass).unwrap();
+        assert_eq!(game.moves, vec![(Stone::White, Command::Pass)]);
+
+        // free placement by black's moves
+        let mut game = Game::new(9, Ruleset::chinese());
+        game.start_free_handicap(2).unwrap();
+        assert_eq!(game.play(Command::Pass), Err(GameError::HandicapLeft(2)));
+        let white = Command::Move {
+            stone: Stone::White,
+            point: Point { row: 1, col: 1 },
+        };
+        assert_eq!(game.play(white), Err(GameError::WrongTurn(Stone::White)));
+        game.play(Command::Move {
+            stone: Stone::Black,
+            point: Point { row: 1, col: 1 },
+        })
+        .unwrap();
+        assert_eq!(game.turn, Stone::Black);
+        assert_eq!(game.free_handicap_left(), 1);
+        assert!(
+            game.play(Command::Move {
+                stone: Stone::Black,
+                point: Point { row: 1, col: 1 },
+            })
+            .is_err()
+        );
+        game.play(Command::Move {
+            stone: Stone::Black,
+            point: Point { row: 5, col: 5 },
+        })
+        .unwrap();
+        assert_eq!(game.free_handicap_left(), 0);
+        assert_eq!(game.turn, Stone::White);
+        assert_eq!(game.setup.len(), 2);
+        assert!(game.moves.is_empty());
+
+        assert!(
+            Game::new(9, Ruleset::chinese())
+                .set_fixed_handicap(10)
+                .is_err()
+        );
+        assert_eq!(
+            Game::new(9, Ruleset::chinese()).start_free_handicap(1),
+            Err(GameError::InvalidHandicap(1))
+        );
+    }
+
+    #[test]
+    fn game_undo_redo() {
+        let mut game = Game::new(9, Ruleset::default());
+        assert_eq!(game.undo(), Err(GameError::NoMoveToUndo));
+        assert_eq!(game.redo(), Err(GameError::NoMoveToRedo));
+        let black = Command::Move {
+            stone: Stone::Black,
+            point: Point { row: 3, col: 3 },
+        };
+        game.play(black).unwrap();
+        game.play(Command::Pass).unwrap();
+        game.play(Command::Pass).unwrap();
+        assert_eq!(game.status, GameStatus::Scoring);
+
+        // the game goes on again after the last pass is taken back
+        game.undo().unwrap();
+        assert_eq!(game.status, GameStatus::Continue);
+        assert_eq!(game.turn, Stone::Black);
+        assert_eq!(game.pass_count, 1);
+        game.undo().unwrap();
+        game.undo().unwrap();
+        assert_eq!(game.turn, Stone::Black);
+        assert_eq!(game.pass_count, 0);
+        assert!(game.moves.is_empty());
+        assert_eq!(game.board, Board::new(9));
+
+        // redo replays in the same order
+        game.redo().unwrap();
+        game.redo().unwrap();
+        assert_eq!(
+            game.moves,
+            vec![(Stone::Black, black), (Stone::White, Command::Pass)]
+        );
+        assert!(game.board.get(Point { row: 3, col: 3 }).is_stone());
+        assert_eq!(game.turn, Stone::Black);
+
+        // a new move drops the moves taken back
+        game.play(Command::Pass).unwrap();
+        assert_eq!(game.status, GameStatus::Scoring);
+        assert_eq!(game.redo(), Err(GameError::Scoring));
+        assert_eq!(game.play(Command::Pass), Err(GameError::Scoring));
+    }
+
+    #[test]
+    fn game_undo_after_resume() {
+        let mut game = Game::new(9, Ruleset::default());
+        game.play(Command::Pass).unwrap();
+        game.play(Command::Pass).unwrap();
+        game.resume().unwrap();
+        game.play(Command::Move {
+            stone: Stone::Black,
+            point: Point { row: 3, col: 3 },
+        })
+        .unwrap();
+
+        // the passes before the resume do not start the scoring again
+        game.undo().unwrap();
+        assert_eq!(game.status, GameStatus::Continue);
+        assert_eq!(game.pass_count, 0);
+        assert_eq!(game.turn, Stone::Black);
+        game.play(Command::Pass).unwrap();
+        assert_eq!(game.status, GameStatus::Continue);
+        game.play(Command::Pass).unwrap();
+        assert_eq!(game.status, GameStatus::Scoring);
+
+        // taking back a pass before the resume takes back the resume too
+        game.undo().unwrap();
+        game.undo().unwrap();
+        assert_eq!(game.status, GameStatus::Continue);
+        game.undo().unwrap();
+        assert_eq!(game.status, GameStatus::Continue);
+        assert_eq!(game.pass_count, 1);
+        game.play(Command::Pass).unwrap();
+        assert_eq!(game.status, GameStatus::Scoring);
+    }
+
+    #[test]
+    fn game_resign_and_forfeit() {
+        let mut game = Game::new(9, Ruleset::default());
+        game.play(Command::Pass).unwrap();
+        game.play(Command::Resign).unwrap();
+        assert_eq!(game.status, GameStatus::End);
+        let result = game.info.result.unwrap();
+        assert_eq!(result.winner, Winner::Black);
+        assert_eq!(result.reason, EndReason::Resign);
+        assert_eq!(result.margin(), None);
+        assert_eq!(result.to_string(), "B+R");
+        assert_eq!(game.moves.last(), Some(&(Stone::White, Command::Resign)));
+
+        // taking back the resignation continues the game
+        game.undo().unwrap();
+        assert_eq!(game.status, GameStatus::Continue);
+        assert_eq!(game.info.result, None);
+        assert_eq!(game.turn, Stone::White);
+
+        game.forfeit(Stone::White, EndReason::Time);
+        assert_eq!(game.info.result.unwrap().to_string(), "B+T");
+        game.forfeit(Stone::Black, EndReason::Forfeit);
+        assert_eq!(game.info.result.unwrap().to_string(), "W+F");
+
+        // a forfeit is not taken back, nor the move before it
+        assert_eq!(game.undo(), Err(GameError::GameOver));
+        assert_eq!(game.status, GameStatus::End);
+        assert_eq!(game.info.result.unwrap().to_string(), "W+F");
+        assert_eq!(game.moves, vec![(Stone::Black, Command::Pass)]);
+    }
+
+    #[test]
+    fn game_result_format() {
+        for text in ["B+3.5", "W+0.5", "B+R", "W+T", "B+F", "0"] {
+            assert_eq!(text.parse::<GameResult>().unwrap().to_string(), text);
+        }
+        assert_eq!("W+Resign".parse::<GameResult>().unwrap().to_string(), "W+R");
+        assert_eq!("B+Time".parse::<GameResult>().unwrap().to_string(), "B+T");
+        assert_eq!("Draw".parse::<GameResult>().unwrap().winner, Winner::Draw);
+        assert_eq!("W+12".parse::<GameResult>().unwrap().margin(), Some(12.0));
+        for text in ["", "?", "Void", "B+", "X+R", "B+abc"] {
+            assert!(text.parse::<GameResult>().is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn game_time_forfeit() {
+        let time = Arc::new(MockTime::default());
+        let mut game = Game::new(9, Ruleset::default());
+        game.clock = Some(Clock::new(
+            TimeControl::ByoYomi {
+                main: Duration::from_secs(10),
+                period: Duration::from_secs(5),
+                periods: 1,
+            },
+            time.clone(),
+        ));
+        assert_eq!(game.time_for_move(), Some(Duration::from_secs(15)));
+        time.advance(Duration::from_secs(12));
+        assert_eq!(game.time_for_move(), Some(Duration::from_secs(3)));
+        game.play(Command::Pass).unwrap();
+
+        // the clock of white started after black's move
+        let clock = game.clock.as_ref().unwrap();
+        assert_eq!(clock.black.main, Duration::ZERO);
+        assert_eq!(clock.black.periods, 1);
+        assert_eq!(clock.white.main, Duration::from_secs(10));
+        time.advance(Duration::from_secs(16));
+        assert_eq!(game.time_for_move(), Some(Duration::ZERO));
+        assert_eq!(
+            game.play(Command::Pass),
+            Err(GameError::TimeOver(Stone::White))
+        );
+        assert_eq!(game.status, GameStatus::End);
+        assert_eq!(game.info.result.unwrap().to_string(), "B+T");
+        assert_eq!(game.moves.len(), 1);
+        assert_eq!(game.undo(), Err(GameError::GameOver));
+        assert_eq!(game.moves.len(), 1);
+    }
+
+    #[test]
+    fn game_is_send() {
+        // a game with a clock can be moved to another thread
+        fn send<T: Send>(_: T) {}
+        let mut game = Game::new(9, Ruleset::default());
+        game.clock = Some(Clock::new(
+            TimeControl::Absolute {
+                main: Duration::from_secs(10),
+            },
+            Arc::new(MockTime::default()),
+        ));
+        send(game);
+    }
+
+    #[test]
+    fn game_turn_order() {
+        let mut game = Game::new(3, Ruleset::default());
+        let white = Command::Move {
+            stone: Stone::White,
+            point: Point { row: 1, col: 1 },
+        };
+        assert_eq!(game.play(white), Err(GameError::WrongTurn(Stone::White)));
+        assert!(game.moves.is_empty());
+        assert_eq!(game.turn, Stone::Black);
+
+        // out of turn only by play_as
+        game.play_as(Stone::White, white).unwrap();
+        assert_eq!(game.turn, Stone::Black);
+        assert_eq!(
+            game.play_as(Stone::Black, white),
+            Err(GameError::WrongTurn(Stone::White))
+        );
+        // the turn is kept after an illegal move
+        assert!(game.play_as(Stone::White, white).is_err());
+        assert_eq!(game.turn, Stone::Black);
+
+        // ┌─────
+        // │   ① ② ③
+        // │ ① ● ┬─┐
+        let commands = game.legal_commands();
+        assert_eq!(commands.len(), 8 + 1);
+        assert!(commands.contains(&Command::Pass));
+        assert!(!commands.contains(&Command::Move {
+            stone: Stone::Black,
+            point: Point { row: 1, col: 1 },
+        }));
+        assert!(
+            commands
+                .iter()
+                .all(|&command| game.clone().play(command).is_ok())
+        );
+
+        game.play(Command::Pass).unwrap();
+        game.play(Command::Pass).unwrap();
+        assert!(game.legal_commands().is_empty());
+
+        // only black's stones while placing handicap
+        let mut game = Game::new(9, Ruleset::default());
+        game.start_free_handicap(2).unwrap();
+        let commands = game.legal_commands();
+        assert_eq!(commands.len(), 81);
+        assert!(!commands.contains(&Command::Pass));
+    }
 }
@@ -0,0 +1,537 @@
+// GTP (Go Text Protocol) version 2 engine.
+// https://www.lysator.liu.se/~gunnar/gtp/gtp2-spec-draft2/gtp2-spec.html
+use crate::board::{Board, Point, Stone};
+use crate::bot::Bot;
+use crate::game::{Command, Game, GameError, GameStatus};
+use crate::rules::Ruleset;
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+
+// GTP coordinates skip "I" to avoid confusion with "J"
+const COLUMNS: &str = "ABCDEFGHJKLMNOPQRSTUVWXYZ";
+
+const COMMANDS: [&str; 18] = [
+    "boardsize",
+    "clear_board",
+    "final_score",
+    "final_status_list",
+    "fixed_handicap",
+    "genmove",
+    "known_command",
+    "komi",
+    "list_commands",
+    "name",
+    "place_free_handicap",
+    "play",
+    "protocol_version",
+    "quit",
+    "set_free_handicap",
+    "showboard",
+    "undo",
+    "version",
+];
+
+pub struct GtpEngine {
+    pub game: Game,
+    bot: Box<dyn Bot>,
+}
+
+// the response is sent as "=id message" on success and "?id message" on failure
+type Response = Result<String, String>;
+
+impl GtpEngine {
+    pub fn new(size: usize, bot: Box<dyn Bot>) -> Self {
+        GtpEngine {
+            game: Game::new(size, Ruleset::default()),
+            bot,
+        }
+    }
+
+    // read commands until "quit" or the end of the input
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            let Some((id, name, args)) = parse_line(&line) else {
+                continue;
+            };
+            let response = self.execute(&name, &args);
+            let id = id.map(|id| id.to_string()).unwrap_or_default();
+            let (status, message) = match response {
+                Ok(message) => ('=', message),
+                Err(message) => ('?', message),
+            };
+            if message.is_empty() {
+                write!(output, "{}{}\n\n", status, id)?;
+            } else {
+                write!(output, "{}{} {}\n\n", status, id, message)?;
+            }
+            output.flush()?;
+            if name == "quit" {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn execute(&mut self, name: &str, args: &[&str]) -> Response {
+        match name {
+            "protocol_version" => Ok("2".to_string()),
+            "name" => Ok("gorust".to_string()),
+            "version" => Ok(env!("CARGO_PKG_VERSION").to_string()),
+            "known_command" => {
+                let command = args.first().ok_or("missing command name")?;
+                Ok(COMMANDS.contains(command).to_string())
+            }
+            "list_commands" => Ok(COMMANDS.join("\n")),
+            "quit" => Ok(String::new()),
+            "boardsize" => {
+                let size = args
+                    .first()
+                    .and_then(|arg| arg.parse::<usize>().ok())
+                    .ok_or("boardsize not an integer")?;
+                if !Board::is_valid_size(size) {
+                    return Err("unacceptable size".to_string());
+                }
+                self.game = self.new_game(size);
+                Ok(String::new())
+            }
+            "clear_board" => {
+                self.game = self.new_game(self.game.board.size());
+                Ok(String::new())
+            }
+            "komi" => {
+                let komi = args
+                    .first()
+                    .and_then(|arg| arg.parse::<f32>().ok())
+                    .filter(|komi| komi.is_finite())
+                    .ok_or("syntax error")?;
+                self.game.set_ruleset(Ruleset {
+                    komi,
+                    ..*self.game.ruleset()
+                });
+                Ok(String::new())
+            }
+            "fixed_handicap" | "place_free_handicap" => {
+                let handicap = args
+                    .first()
+                    .and_then(|arg| arg.parse::<usize>().ok())
+                    .ok_or("handicap not an integer")?;
+                // the engine chooses the same points as fixed handicap for free placement
+                let points = self
+                    .game
+                    .board
+                    .handicap_points(handicap)
+                    .map_err(|_| "invalid number of stones")?;
+                self.set_handicap(&points)?;
+                let size = self.game.board.size();
+                Ok(points
+                    .iter()
+                    .map(|&point| format_vertex(point, size))
+                    .collect::<Vec<_>>()
+                    .join(" "))
+            }
+            "set_free_handicap" => {
+                let size = self.game.board.size();
+                let points = args
+                    .iter()
+                    .map(|arg| parse_vertex(arg, size).flatten())
+                    .collect::<Option<Vec<Point>>>()
+                    .ok_or("bad vertex list")?;
+                self.set_handicap(&points)?;
+                Ok(String::new())
+            }
+            "play" => {
+                let [color, vertex] = args[..] else {
+                    return Err("invalid color or coordinate".to_string());
+                };
+                let stone = parse_color(color).ok_or("invalid color or coordinate")?;
+                let command = match parse_vertex(vertex, self.game.board.size())
+                    .ok_or("invalid color or coordinate")?
+                {
+                    Some(point) => Command::Move { stone, point },
+                    None => Command::Pass,
+                };
+                self.resume_if_scoring();
+                // GTP allows a color to play out of turn
+                self.game
+                    .play_as(stone, command)
+                    .map_err(|_| "illegal move".to_string())?;
+                Ok(String::new())
+            }
+            "genmove" => {
+                let stone = args
+                    .first()
+                    .and_then(|arg| parse_color(arg))
+                    .ok_or("invalid color")?;
+                self.resume_if_scoring();
+                // the bot thinks for the color, which may be out of turn
+                let turn = self.game.turn;
+                self.game.turn = stone;
+                let command = self.bot.next_command(&self.game);
+                self.game.turn = turn;
+                self.game
+                    .play_as(stone, command)
+                    .map_err(|err| err.to_string())?;
+                match command {
+                    Command::Move { point, .. } => Ok(format_vertex(point, self.game.board.size())),
+                    Command::Pass => Ok("pass".to_string()),
+                    Command::Resign => Ok("resign".to_string()),
+                }
+            }
+            "undo" => {
+                self.game.undo().map_err(|_| "cannot undo")?;
+                Ok(String::new())
+            }
+            // a resigned game keeps its result. the scoring is left to the controller, which may
+            // play on after the query when the players disagree
+            "final_score" => match self.game.info.result {
+                Some(result) => Ok(result.to_string()),
+                None => {
+                    let dead_stones = self.final_dead_stones();
+                    Ok(self
+                        .game
+                        .score(self.game.ruleset().scoring, &dead_stones)
+                        .to_string())
+                }
+            },
+            // one group a line. seki is not told from alive
+            "final_status_list" => {
+                let dead = match args.first() {
+                    Some(&"alive") => false,
+                    Some(&"dead") => true,
+                    Some(&"seki") => return Ok(String::new()),
+                    _ => return Err("invalid status".to_string()),
+                };
+                let dead_stones = self.final_dead_stones();
+                let board = &self.game.board;
+                let mut listed = HashSet::new();
+                let mut lines = vec![];
+                for point in board.points() {
+                    if listed.contains(&point) || !board.get(point).is_stone() {
+                        continue;
+                    }
+                    let group = board.group_at(point);
+                    listed.extend(group.iter().copied());
+                    if dead_stones.contains(&point) == dead {
+                        let vertices = group
+                            .iter()
+                            .map(|&p| format_vertex(p, board.size()))
+                            .collect::<Vec<_>>();
+                        lines.push(vertices.join(" "));
+                    }
+                }
+                Ok(lines.join("\n"))
+            }
+            // an empty line would end the response
+            "showboard" => Ok(format!("\n{}", self.game.board.to_string().trim_end())),
+            _ => Err("unknown command".to_string()),
+        }
+    }
+
+    fn set_handicap(&mut self, points: &[Point]) -> Result<(), String> {
+        // komi is given by the controller
+        let ruleset = *self.game.ruleset();
+        self.game
+            .set_free_handicap(points)
+            .map_err(|err| match err {
+                GameError::BoardNotEmpty => "board not empty",
+                _ => "bad vertex list",
+            })?;
+        self.game.set_ruleset(ruleset);
+        Ok(())
+    }
+
+    // the controller plays on after passes when the players disagree about the dead stones
+    fn resume_if_scoring(&mut self) {
+        if self.game.status == GameStatus::Scoring {
+            self.game
+                .resume()
+                .expect("the game is in the scoring phase");
+        }
+    }
+
+    // the marked dead stones, or the guess of the bot while none are marked
+    fn final_dead_stones(&mut self) -> HashSet<Point> {
+        if self.game.status == GameStatus::End || !self.game.dead_stones().is_empty() {
+            self.game.dead_stones().clone()
+        } else {
+            self.bot.dead_stones(&self.game)
+        }
+    }
+
+    // empty board keeping the settings of the current game
+    fn new_game(&self, size: usize) -> Game {
+        let mut game = Game::new(size, *self.game.ruleset());
+        game.info.black_player = self.game.info.black_player.clone();
+        game.info.white_player = self.game.info.white_player.clone();
+        game
+    }
+}
+
+// split a line into (id, command name, arguments) after removing comments and control characters
+fn parse_line(line: &str) -> Option<(Option<u32>, String, Vec<&str>)> {
+    let line = line.split('#').next().unwrap_or_default();
+    let mut words = line
+        .split(|c: char| c.is_whitespace() || c.is_control())
+        .filter(|word| !word.is_empty())
+        .peekable();
+    let id = words.next_if(|word| word.parse::<u32>().is_ok());
+    let id = id.map(|id| id.parse::<u32>().unwrap());
+    let name = words.next()?.to_string();
+    Some((id, name, words.collect()))
+}
+
+fn parse_color(color: &str) -> Option<Stone> {
+    match color.to_lowercase().as_str() {
+        "b" | "black" => Some(Stone::Black),
+        "w" | "white" => Some(Stone::White),
+        _ => None,
+    }
+}
+
+// "D4" counts columns from the left and rows from the bottom.
+// returns Some(None) for pass.
+pub fn parse_vertex(vertex: &str, size: usize) -> Option<Option<Point>> {
+    let vertex = vertex.to_uppercase();
+    if vertex == "PASS" {
+        return Some(None);
+    }
+    let mut chars = vertex.chars();
+    let col = COLUMNS.find(chars.next()?)? + 1;
+    let number = chars.as_str().parse::<usize>().ok()?;
+    if col > size || number == 0 || number > size {
+        return None;
+    }
+    Some(Some(Point {
+        row: (size + 1 - number) as i8,
+        col: col as i8,
+    }))
+}
+
+pub fn format_vertex(point: Point, size: usize) -> String {
+    let column = COLUMNS.as_bytes()[point.col as usize - 1] as char;
+    format!("{}{}", column, size + 1 - point.row as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::RandomBot;
+    use crate::game::GameStatus;
+
+    fn run(engine: &mut GtpEngine, input: &str) -> String {
+        let mut output = vec![];
+        engine.run(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn gtp_vertex() {
+        assert_eq!(parse_vertex("A1", 9), Some(Some(Point { row: 9, col: 1 })));
+        assert_eq!(parse_vertex("j9", 9), Some(Some(Point { row: 1, col: 9 })));
+        assert_eq!(
+            parse_vertex("T19", 19),
+            Some(Some(Point { row: 1, col: 19 }))
+        );
+        assert_eq!(parse_vertex("pass", 9), Some(None));
+        assert_eq!(parse_vertex("I5", 9), None);
+        assert_eq!(parse_vertex("K1", 9), None);
+        assert_eq!(parse_vertex("A10", 9), None);
+        assert_eq!(parse_vertex("A0", 9), None);
+        for size in [9, 19, 25] {
+            let board = Board::new(size);
+            for point in board.points() {
+                let vertex = format_vertex(point, size);
+                assert_eq!(parse_vertex(&vertex, size), Some(Some(point)));
+            }
+        }
+    }
+
+    #[test]
+    fn gtp_session() {
+        let mut engine = GtpEngine::new(9, Box::new(RandomBot::new()));
+        let output = run(
+            &mut engine,
+            "1 protocol_version\n\
+             # comment line\n\
+             \n\
+             2 boardsize 5\n\
+             komi 0.5 # trailing comment\n\
+             play black C3\n\
+             play white C3\n\
+             3 play w B3\n\
+             known_command genmove\n\
+             unknown_command\n\
+             quit\n\
+             name\n",
+        );
+        assert_eq!(
+            output,
+            "=1 2\n\n\
+             =2\n\n\
+             =\n\n\
+             =\n\n\
+             ? illegal move\n\n\
+             =3\n\n\
+             = true\n\n\
+             ? unknown command\n\n\
+             =\n\n"
+        );
+        assert_eq!(engine.game.board.size(), 5);
+        assert_eq!(engine.game.ruleset().komi, 0.5);
+        assert_eq!(engine.game.moves.len(), 2);
+    }
+
+    #[test]
+    fn gtp_genmove_and_undo() {
+        let mut engine = GtpEngine::new(9, Box::new(RandomBot::new()));
+        assert!(engine.execute("play", &["b", "E5"]).is_ok());
+        let vertex = engine.execute("genmove", &["w"]).unwrap();
+        assert!(matches!(parse_vertex(&vertex, 9), Some(Some(_))));
+        assert_eq!(engine.game.turn, Stone::Black);
+
+        assert!(engine.execute("undo", &[]).is_ok());
+        assert_eq!(engine.game.moves.len(), 1);
+        assert_eq!(engine.game.turn, Stone::White);
+        assert!(engine.execute("undo", &[]).is_ok());
+        assert!(engine.execute("undo", &[]).is_err());
+        assert_eq!(engine.game.board, Board::new(9));
+
+        // undo the second pass to continue the game
+        engine.execute("play", &["b", "pass"]).unwrap();
+        engine.execute("play", &["w", "pass"]).unwrap();
+        assert_eq!(engine.game.status, GameStatus::Scoring);
+        engine.execute("undo", &[]).unwrap();
+        assert_eq!(engine.game.status, GameStatus::Continue);
+    }
+
+    #[test]
+    fn gtp_genmove_after_game_over() {
+        let mut engine = GtpEngine::new(9, Box::new(RandomBot::new()));
+        engine.execute("play", &["b", "E5"]).unwrap();
+        engine.game.play(Command::Resign).unwrap();
+        let turn = engine.game.turn;
+        assert!(engine.execute("genmove", &["b"]).is_err());
+        assert!(engine.execute("genmove", &["w"]).is_err());
+        assert_eq!(engine.game.turn, turn);
+        assert_eq!(engine.game.moves.len(), 2);
+    }
+
+    #[test]
+    fn gtp_handicap() {
+        let mut engine = GtpEngine::new(19, Box::new(RandomBot::new()));
+        engine.execute("komi", &["6.5"]).unwrap();
+        assert_eq!(
+            engine.execute("fixed_handicap", &["4"]),
+            Ok("D4 Q16 D16 Q4".to_string())
+        );
+        assert_eq!(engine.game.turn, Stone::White);
+        assert_eq!(engine.game.ruleset().komi, 6.5);
+        assert_eq!(
+            engine.execute("fixed_handicap", &["2"]),
+            Err("board not empty".to_string())
+        );
+        // undo does not remove handicap stones
+        engine.execute("genmove", &["w"]).unwrap();
+        engine.execute("undo", &[]).unwrap();
+        assert_eq!(engine.game.setup.len(), 4);
+        assert_eq!(engine.game.turn, Stone::White);
+
+        engine.execute("clear_board", &[]).unwrap();
+        assert!(engine.execute("set_free_handicap", &["A1", "A1"]).is_err());
+        assert!(engine.execute("set_free_handicap", &["A1", "Z9"]).is_err());
+        engine
+            .execute("set_free_handicap", &["A1", "T19", "K10"])
+            .unwrap();
+        assert_eq!(engine.game.handicap, 3);
+        assert!(engine.execute("fixed_handicap", &["10"]).is_err());
+    }
+
+    #[test]
+    fn gtp_final_status_list() {
+        let mut engine = GtpEngine::new(9, Box::new(RandomBot::new()));
+        for (color, vertex) in [("b", "E5"), ("w", "D4"), ("b", "E6")] {
+            engine.execute("play", &[color, vertex]).unwrap();
+        }
+        // the random bot takes every stone for alive
+        let alive = engine.execute("final_status_list", &["alive"]).unwrap();
+        let mut groups = alive.lines().collect::<Vec<_>>();
+        groups.sort();
+        assert_eq!(groups, vec!["D4", "E6 E5"]);
+        assert_eq!(
+            engine.execute("final_status_list", &["dead"]),
+            Ok(String::new())
+        );
+        assert_eq!(
+            engine.execute("final_status_list", &["seki"]),
+            Ok(String::new())
+        );
+        assert!(engine.execute("final_status_list", &["ko"]).is_err());
+        assert!(engine.execute("final_status_list", &[]).is_err());
+
+        // the passes start the scoring, the lists and the score leave it to the controller
+        engine.execute("play", &["w", "pass"]).unwrap();
+        engine.execute("play", &["b", "pass"]).unwrap();
+        assert_eq!(engine.game.status, GameStatus::Scoring);
+        assert!(engine.execute("final_status_list", &["alive"]).is_ok());
+        assert!(engine.execute("final_score", &[]).is_ok());
+        assert_eq!(engine.game.status, GameStatus::Scoring);
+        assert_eq!(engine.game.info.result, None);
+    }
+
+    #[test]
+    fn gtp_final_score_after_clear_board() {
+        let mut engine = GtpEngine::new(9, Box::new(RandomBot::new()));
+        engine.execute("komi", &["6.5"]).unwrap();
+        engine.game.play(Command::Resign).unwrap();
+        assert_eq!(engine.execute("final_score", &[]), Ok("W+R".to_string()));
+
+        // the result of the last game is not carried to the next
+        engine.execute("clear_board", &[]).unwrap();
+        assert_eq!(engine.execute("final_score", &[]), Ok("W+6.5".to_string()));
+        engine.game.play(Command::Resign).unwrap();
+        engine.execute("boardsize", &["13"]).unwrap();
+        assert_eq!(engine.execute("final_score", &[]), Ok("W+6.5".to_string()));
+    }
+
+    #[test]
+    fn gtp_play_after_final_status_list() {
+        // a server asks for the dead stones after two passes, then the players play on
+        let mut engine = GtpEngine::new(9, Box::new(RandomBot::new()));
+        engine.execute("play", &["b", "pass"]).unwrap();
+        engine.execute("play", &["w", "pass"]).unwrap();
+        assert_eq!(
+            engine.execute("final_status_list", &["dead"]),
+            Ok(String::new())
+        );
+        assert_eq!(engine.execute("play", &["b", "E5"]), Ok(String::new()));
+        assert_eq!(engine.game.status, GameStatus::Continue);
+        assert_eq!(engine.game.moves.len(), 3);
+        assert!(engine.execute("genmove", &["w"]).is_ok());
+    }
+
+    #[test]
+    fn gtp_final_score() {
+        let mut engine = GtpEngine::new(9, Box::new(RandomBot::new()));
+        engine.execute("komi", &["6.5"]).unwrap();
+        engine.execute("play", &["b", "E5"]).unwrap();
+        engine.execute("play", &["w", "D4"]).unwrap();
+        assert_eq!(engine.execute("final_score", &[]), Ok("W+6.5".to_string()));
+        assert!(engine.execute("boardsize", &["30"]).is_err());
+        for komi in ["abc", "nan", "inf", "-inf"] {
+            assert_eq!(
+                engine.execute("komi", &[komi]),
+                Err("syntax error".to_string())
+            );
+        }
+        assert_eq!(engine.game.ruleset().komi, 6.5);
+        assert!(engine.execute("play", &["b"]).is_err());
+        assert!(engine.execute("play", &["red", "A1"]).is_err());
+        assert!(
+            engine
+                .execute("showboard", &[])
+                .unwrap()
+                .contains(&Stone::White.to_string())
+        );
+    }
+}
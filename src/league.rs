@@ -0,0 +1,288 @@
+// round-robin league of bots rated by the Bradley-Terry model on the Elo scale.
+// results are kept in a text file, one game per line: "<black> <white> <result>"
+use crate::board::Stone;
+use crate::bot::Bot;
+use crate::tournament::Match;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+
+// a virtual draw between every pair of bots keeps the ratings of unbeaten bots finite
+const PRIOR_DRAWS: f32 = 1.0;
+const MAX_ITERATIONS: usize = 1000;
+const TOLERANCE: f32 = 1e-6;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeagueGame {
+    pub black: String,
+    pub white: String,
+    // in SGF RE format. e.g. "B+3.5", "W+F", "0"
+    pub result: String,
+}
+
+impl LeagueGame {
+    // 1 for a win of black, 0 for a win of white
+    fn black_score(&self) -> f32 {
+        if self.result.starts_with("B+") {
+            1.0
+        } else if self.result.starts_with("W+") {
+            0.0
+        } else {
+            0.5
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rating {
+    pub name: String,
+    // Elo difference from the average bot
+    pub elo: f32,
+    pub games: usize,
+    // a draw counts as half a win
+    pub wins: f32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct League {
+    pub games: Vec<LeagueGame>,
+}
+
+impl League {
+    // a missing file is an empty league
+    pub fn load(path: &str) -> Result<League, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(format!("failed to read {}: {}", path, err)),
+        };
+        League::parse(&text)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_string())
+            .map_err(|err| format!("failed to write {}: {}", path, err))
+    }
+
+    pub fn parse(text: &str) -> Result<League, String> {
+        let games = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(
+                |line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+                    [black, white, result] => Ok(LeagueGame {
+                        black: black.to_string(),
+                        white: white.to_string(),
+                        result: result.to_string(),
+                    }),
+                    _ => Err(format!("invalid league game: {}", line)),
+                },
+            )
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(League { games })
+    }
+
+    // every pair of bots plays the given number of games, alternating colors
+    pub fn play_round_robin(
+        &mut self,
+        bots: &mut [(&str, Box<dyn Bot>)],
+        games: usize,
+        tournament: &Match,
+    ) {
+        for i in 0..bots.len() {
+            let (former, latter) = bots.split_at_mut(i + 1);
+            let (first_name, first) = &mut former[i];
+            for (second_name, second) in latter.iter_mut() {
+                let (first_name, second_name) = (*first_name, *second_name);
+                for round in 0..games {
+                    let game = tournament.play_game(round, first.as_mut(), second.as_mut());
+                    let (black, white) = match game.color {
+                        Stone::Black => (first_name, second_name),
+                        Stone::White => (second_name, first_name),
+                    };
+                    self.games.push(LeagueGame {
+                        black: black.to_string(),
+                        white: white.to_string(),
+                        result: game.result.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    // ratings from all the games by the MM algorithm of Bradley-Terry model, best first
+    pub fn ratings(&self) -> Vec<Rating> {
+        let mut names = BTreeMap::new();
+        for game in self.games.iter() {
+            for name in [&game.black, &game.white] {
+                let index = names.len();
+                names.entry(name.clone()).or_insert(index);
+            }
+        }
+        let n = names.len();
+        // number of games and wins between each pair
+        let mut played = vec![vec![0.0; n]; n];
+        let mut wins = vec![0.0; n];
+        let mut games = vec![0; n];
+        for game in self.games.iter() {
+            let (black, white) = (names[&game.black], names[&game.white]);
+            played[black][white] += 1.0;
+            played[white][black] += 1.0;
+            wins[black] += game.black_score();
+            wins[white] += 1.0 - game.black_score();
+            games[black] += 1;
+            games[white] += 1;
+        }
+        let real_wins = wins.clone();
+        for (i, opponents) in played.iter_mut().enumerate() {
+            for games in opponents.iter_mut().filter(|games| **games > 0.0) {
+                *games += PRIOR_DRAWS;
+                wins[i] += PRIOR_DRAWS / 2.0;
+            }
+        }
+
+        // strength of each bot. the probability that i beats j is gamma_i / (gamma_i + gamma_j)
+        let mut gamma = vec![1.0f32; n];
+        for _ in 0..MAX_ITERATIONS {
+            let mut next = (0..n)
+                .map(|i| {
+                    let denominator = (0..n)
+                        .filter(|&j| j != i)
+                        .map(|j| played[i][j] / (gamma[i] + gamma[j]))
+                        .sum::<f32>();
+                    if denominator > 0.0 {
+                        wins[i] / denominator
+                    } else {
+                        1.0
+                    }
+                })
+                .collect::<Vec<_>>();
+            // the geometric mean is fixed to 1, i.e. the average rating to 0
+            let mean = next.iter().map(|g| g.ln()).sum::<f32>() / n as f32;
+            next.iter_mut().for_each(|g| *g /= mean.exp());
+            let change = (0..n)
+                .map(|i| (next[i] - gamma[i]).abs())
+                .fold(0.0, f32::max);
+            gamma = next;
+            if change < TOLERANCE {
+                break;
+            }
+        }
+
+        let mut ratings = names
+            .into_iter()
+            .map(|(name, i)| Rating {
+                name,
+                elo: 400.0 * gamma[i].log10(),
+                games: games[i],
+                wins: real_wins[i],
+            })
+            .collect::<Vec<_>>();
+        ratings.sort_by(|a, b| b.elo.total_cmp(&a.elo));
+        ratings
+    }
+}
+
+// in the file format
+impl fmt::Display for League {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for game in self.games.iter() {
+            writeln!(f, "{} {} {}", game.black, game.white, game.result)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::RandomBot;
+    use crate::rules::Ruleset;
+
+    fn league(games: &[(&str, &str, &str)]) -> League {
+        League {
+            games: games
+                .iter()
+                .map(|&(black, white, result)| LeagueGame {
+                    black: black.to_string(),
+                    white: white.to_string(),
+                    result: result.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn league_ratings() {
+        let ratings = league(&[
+            ("a", "b", "B+3.5"),
+            ("b", "a", "W+R"),
+            ("a", "b", "B+F"),
+            ("b", "a", "0"),
+        ])
+        .ratings();
+        assert_eq!(ratings[0].name, "a");
+        assert_eq!(ratings[0].games, 4);
+        assert_eq!(ratings[0].wins, 3.5);
+        assert_eq!(ratings[1].name, "b");
+        assert!(ratings[0].elo > 0.0);
+        assert!((ratings[0].elo + ratings[1].elo).abs() < 0.01);
+        // with the prior, a wins 4 of 5 games: 400 * log10(4) / 2 from the average
+        assert!((ratings[0].elo - 120.41).abs() < 0.1);
+
+        // even results make even ratings
+        let ratings = league(&[("a", "b", "B+1"), ("b", "c", "B+1"), ("c", "a", "B+1")]).ratings();
+        assert!(ratings.iter().all(|r| r.elo.abs() < 0.01));
+
+        // a beats b, b beats c
+        let ratings = league(&[("a", "b", "B+1"), ("c", "b", "W+1")]).ratings();
+        let names = ratings.iter().map(|r| r.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn league_file() {
+        let league = league(&[("random", "mcts", "W+12.5"), ("mcts", "random", "B+F")]);
+        let text = league.to_string();
+        assert_eq!(text, "random mcts W+12.5\nmcts random B+F\n");
+        assert_eq!(League::parse(&text), Ok(league.clone()));
+        assert!(League::parse("random mcts").is_err());
+
+        let path = std::env::temp_dir().join(format!("gorust-league-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        assert_eq!(League::load(path), Ok(League::default()));
+        league.save(path).unwrap();
+        assert_eq!(League::load(path), Ok(league));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn league_round_robin() {
+        let mut bots: Vec<(&str, Box<dyn Bot>)> = vec![
+            ("a", Box::new(RandomBot::new())),
+            ("b", Box::new(RandomBot::new())),
+            ("c", Box::new(RandomBot::new())),
+        ];
+        let mut league = League::default();
+        league.play_round_robin(&mut bots, 2, &Match::new(4, Ruleset::default()));
+
+        let pairs = league
+            .games
+            .iter()
+            .map(|g| (g.black.as_str(), g.white.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pairs,
+            vec![
+                ("a", "b"),
+                ("b", "a"),
+                ("a", "c"),
+                ("c", "a"),
+                ("b", "c"),
+                ("c", "b")
+            ]
+        );
+        assert_eq!(league.ratings().len(), 3);
+    }
+}
@@ -0,0 +1,32 @@
+//! go engine: the board and rules, games with clocks and records, bots and their matches.
+//! the types most programs need are re-exported here. e.g.
+//!
+//! ```
+//! use gorust::{Command, Game, GameError, Point, Ruleset, Stone};
+//!
+//! let mut game = Game::new(9, Ruleset::japanese());
+//! let point = Point { row: 3, col: 3 };
+//! game.play(Command::Move { stone: Stone::Black, point })?;
+//! assert_eq!(game.turn, Stone::White);
+//! # Ok::<(), GameError>(())
+//! ```
+pub mod board;
+pub mod bot;
+pub mod clock;
+pub mod frac;
+pub mod game;
+pub mod gtp;
+pub mod league;
+pub mod play;
+pub mod rules;
+pub mod score;
+pub mod sgf;
+pub mod tournament;
+pub mod tui;
+
+pub use board::{Board, BoardError, KoRule, Point, Stone};
+pub use bot::{Bot, MctsBot, RandomBot, SearchBudget};
+pub use clock::{Clock, TimeControl};
+pub use game::{Command, EndReason, Game, GameError, GameResult, GameStatus, Winner};
+pub use rules::Ruleset;
+pub use score::{Score, Scoring};
@@ -1,18 +1,227 @@
-pub mod board;
-pub mod bot;
-pub mod frac;
-pub mod game;
+use gorust::board::{DEFAULT_BOARD_SIZE, Stone};
+use gorust::bot::{self, BOT_NAMES, settle_dead_stones};
+use gorust::gtp::GtpEngine;
+use gorust::league::League;
+use gorust::play::HumanPlay;
+use gorust::tournament::{Match, MatchStats};
+use gorust::tui::Tui;
+use gorust::{Board, Bot, EndReason, Game, GameStatus, RandomBot, Ruleset, Score, Scoring};
 
-use bot::{Bot, RandomBot};
-use game::Game;
-
-use crate::game::GameStatus;
+const USAGE: &str = "\
+usage:
+  gorust selfplay [size] [black seed] [white seed]
+                                        plays RandomBot against RandomBot
+  gorust gtp [bot]                      speaks GTP on stdin/stdout, as random or mcts
+  gorust play [black|white] [bot] [size]
+                                        plays against a bot in the terminal
+  gorust tui [black|white] [bot] [size] plays against a bot on the full screen
+  gorust score <file> [ruleset]         counts the final position of an SGF record
+  gorust tournament <bot> <bot> [games] [size]
+                                        plays a match between bots, e.g. random and mcts
+  gorust league [games] [size] [file]   plays a round-robin of all bots and rates them";
 
 fn main() -> Result<(), String> {
-    let mut game = Game::new();
-    let mut bot_player = RandomBot::new();
-    let mut bot_player2 = RandomBot::new();
+    let args = std::env::args().collect::<Vec<_>>();
+    match args.get(1).map(String::as_str) {
+        Some("selfplay") => {
+            let size = match args.get(2) {
+                Some(arg) => arg
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid board size: {}", arg))?,
+                None => DEFAULT_BOARD_SIZE,
+            };
+            // seeds printed by a previous game replay the game
+            let black_seed = parse_seed(args.get(3))?;
+            let white_seed = parse_seed(args.get(4))?;
+            selfplay(size, black_seed, white_seed)
+        }
+        Some("gtp") => {
+            let bot = create_bot(args.get(2).map_or("random", String::as_str))?;
+            let mut engine = GtpEngine::new(DEFAULT_BOARD_SIZE, bot);
+            engine
+                .run(std::io::stdin().lock(), std::io::stdout().lock())
+                .map_err(|err| err.to_string())
+        }
+        Some("play") => human_play(&args[2..])?
+            .run(std::io::stdin().lock(), std::io::stdout().lock())
+            .map_err(|err| err.to_string()),
+        Some("tui") => Tui::new(human_play(&args[2..])?)
+            .run()
+            .map_err(|err| err.to_string()),
+        Some("score") => score(&args[2..]),
+        Some("tournament") => tournament(&args[2..]),
+        Some("league") => league(&args[2..]),
+        Some("help" | "-h" | "--help") | None => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(arg) => Err(format!("unknown command: {}, see gorust help", arg)),
+    }
+}
+
+fn parse_seed(arg: Option<&String>) -> Result<u64, String> {
+    match arg {
+        Some(arg) => arg
+            .parse::<u64>()
+            .map_err(|_| format!("invalid seed: {}", arg)),
+        None => Ok(rand::random()),
+    }
+}
+
+fn human_play(args: &[String]) -> Result<HumanPlay, String> {
+    let human = match args.first().map(|arg| arg.to_lowercase()).as_deref() {
+        Some("black" | "b") | None => Stone::Black,
+        Some("white" | "w") => Stone::White,
+        Some(arg) => return Err(format!("invalid color: {}", arg)),
+    };
+    let bot = create_bot(args.get(1).map_or("mcts", String::as_str))?;
+    let size = match args.get(2) {
+        Some(arg) => arg
+            .parse::<usize>()
+            .ok()
+            .filter(|&size| Board::is_valid_size(size))
+            .ok_or(format!("invalid board size: {}", arg))?,
+        None => DEFAULT_BOARD_SIZE,
+    };
+    Ok(HumanPlay::new(size, Ruleset::default(), human, bot))
+}
+
+fn create_bot(name: &str) -> Result<Box<dyn Bot>, String> {
+    bot::create_bot(name).ok_or(format!(
+        "unknown bot: {}, choose from {}",
+        name,
+        BOT_NAMES.join(", ")
+    ))
+}
+
+fn score(args: &[String]) -> Result<(), String> {
+    let path = args.first().ok_or("an SGF file is required")?;
+    let sgf =
+        std::fs::read_to_string(path).map_err(|err| format!("failed to read {}: {}", path, err))?;
+    let mut game = Game::from_sgf(&sgf)?;
+    if let Some(name) = args.get(1) {
+        let ruleset = Ruleset::from_name(name).ok_or(format!("unknown ruleset: {}", name))?;
+        game.set_ruleset(Ruleset {
+            scoring: ruleset.scoring,
+            ..*game.ruleset()
+        });
+    }
+    let result = game.game_end();
+    let scoring = match game.ruleset().scoring {
+        Scoring::Area => "area",
+        Scoring::Territory => "territory",
+    };
+    println!("{}", game.board);
+    println!("scoring: {}", scoring);
+    if let EndReason::Score { black, white } = result.reason {
+        println!("black: {}", format_score(&black));
+        println!("white: {}", format_score(&white));
+    }
+    println!("result: {}", result);
+    if let Some(result) = game.info.result {
+        println!("recorded result: {}", result);
+    }
+    Ok(())
+}
+
+fn format_score(score: &Score) -> String {
+    format!(
+        "{} stones + {} territory + {} prisoners + {} komi = {}",
+        score.stones,
+        score.territory,
+        score.prisoners,
+        score.komi,
+        score.total()
+    )
+}
+
+fn tournament(args: &[String]) -> Result<(), String> {
+    let [first_name, second_name, ..] = args else {
+        return Err(format!("two bots are required: {}", BOT_NAMES.join(", ")));
+    };
+    let mut first = create_bot(first_name)?;
+    let mut second = create_bot(second_name)?;
+    let games = match args.get(2) {
+        Some(arg) => arg
+            .parse::<usize>()
+            .map_err(|_| format!("invalid number of games: {}", arg))?,
+        None => 10,
+    };
+    let size = match args.get(3) {
+        Some(arg) => arg
+            .parse::<usize>()
+            .ok()
+            .filter(|&size| Board::is_valid_size(size))
+            .ok_or(format!("invalid board size: {}", arg))?,
+        None => DEFAULT_BOARD_SIZE,
+    };
+
+    let tournament = Match::new(size, Ruleset::default());
+    let mut stats = MatchStats::default();
+    for round in 0..games {
+        let game = tournament.play_game(round, first.as_mut(), second.as_mut());
+        println!(
+            "game {}: {} as {:?}, {} in {} moves",
+            round + 1,
+            first_name,
+            game.color,
+            game.result,
+            game.length
+        );
+        stats.games.push(game);
+    }
+    println!("{} against {}", first_name, second_name);
+    println!("{}", stats);
+    Ok(())
+}
 
+fn league(args: &[String]) -> Result<(), String> {
+    let games = match args.first() {
+        Some(arg) => arg
+            .parse::<usize>()
+            .map_err(|_| format!("invalid number of games: {}", arg))?,
+        None => 2,
+    };
+    let size = match args.get(1) {
+        Some(arg) => arg
+            .parse::<usize>()
+            .ok()
+            .filter(|&size| Board::is_valid_size(size))
+            .ok_or(format!("invalid board size: {}", arg))?,
+        None => DEFAULT_BOARD_SIZE,
+    };
+    let path = args.get(2).map_or("gorust-league.txt", String::as_str);
+
+    // results of the previous leagues are rated together
+    let mut league = League::load(path)?;
+    let mut bots = BOT_NAMES
+        .iter()
+        .map(|&name| Ok((name, create_bot(name)?)))
+        .collect::<Result<Vec<_>, String>>()?;
+    league.play_round_robin(&mut bots, games, &Match::new(size, Ruleset::default()));
+    league.save(path)?;
+
+    println!("{:<10} {:>7} {:>6} {:>6}", "bot", "elo", "games", "wins");
+    for rating in league.ratings() {
+        println!(
+            "{:<10} {:>+7.0} {:>6} {:>6.1}",
+            rating.name, rating.elo, rating.games, rating.wins
+        );
+    }
+    Ok(())
+}
+
+fn selfplay(size: usize, black_seed: u64, white_seed: u64) -> Result<(), String> {
+    if !Board::is_valid_size(size) {
+        return Err(format!("unsupported board size: {}", size));
+    }
+    let mut game = Game::new(size, Ruleset::default());
+    let mut bot_player = RandomBot::with_seed(white_seed);
+    let mut bot_player2 = RandomBot::with_seed(black_seed);
+
+    // replay the game by RandomBot::with_seed
+    println!("black seed: {}", bot_player2.seed());
+    println!("white seed: {}", bot_player.seed());
     println!("game start.");
     println!("{}", game.board);
 
@@ -33,28 +242,28 @@ fn main() -> Result<(), String> {
         // play computer
         println!("{:?}: computer's turn.", game.turn);
         let command = bot_player2.next_command(&game);
-        if game.play(command).is_err() {
-            panic!("error");
-        };
+        game.play(command).map_err(|err| err.to_string())?;
         println!("command: {:?}", command);
         println!("{}", game.board);
-        if matches!(game.status, GameStatus::End) {
+        if game.status != GameStatus::Continue {
             break;
         }
 
         // play computer
         println!("{:?}: computer's turn.", game.turn);
         let command = bot_player.next_command(&game);
-        if game.play(command).is_err() {
-            panic!("error");
-        }
+        game.play(command).map_err(|err| err.to_string())?;
         println!("command: {:?}", command);
         println!("{}", game.board);
-        if matches!(game.status, GameStatus::End) {
+        if game.status != GameStatus::Continue {
             break;
         }
     }
+    settle_dead_stones(&mut game, &mut bot_player2, &mut bot_player);
+    println!("black prisoners: {:?}", game.board.black_prisoners);
+    println!("white prisoners: {:?}", game.board.white_prisoners);
     println!("{:?}", game.game_end());
+    println!("{}", game.to_sgf());
     println!("game end.");
 
     Ok(())
@@ -0,0 +1,409 @@
+// interactive game between a human and a bot in the terminal
+use crate::board::{BoardError, Point, Stone};
+use crate::bot::Bot;
+use crate::game::{Command, EndReason, Game, GameError, GameStatus};
+use crate::gtp::{format_vertex, parse_vertex};
+use crate::rules::Ruleset;
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+
+const HELP: &str = "\
+commands:
+  3,4     put a stone on row 3 and column 4 as numbered on the board
+  D4      put a stone on column D and row 4 from the bottom, as GTP
+  pass    pass the turn
+  resign  resign the game
+  undo    take back your last move
+  hint    ask the computer for a move
+  help    show this message
+  quit    quit the game
+after both players pass:
+  3,4     mark or unmark the group on the point as dead
+  done    accept the dead stones
+  resume  play on instead";
+
+const SCORING_HELP: &str =
+    "mark or unmark dead groups by their points, done to accept, resume to play on";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Input {
+    Move(Point),
+    Pass,
+    Resign,
+    Undo,
+    Hint,
+    Help,
+    Quit,
+    // accept the dead stones
+    Done,
+    Resume,
+}
+
+// both "row,col" as numbered on the board and GTP vertices like "D4" are accepted
+pub fn parse_input(input: &str, size: usize) -> Result<Input, String> {
+    let input = input.trim();
+    match input.to_lowercase().as_str() {
+        "" => return Err("enter a move, or help for commands".to_string()),
+        "pass" => return Ok(Input::Pass),
+        "resign" => return Ok(Input::Resign),
+        "undo" => return Ok(Input::Undo),
+        "hint" => return Ok(Input::Hint),
+        "done" => return Ok(Input::Done),
+        "resume" => return Ok(Input::Resume),
+        "help" => return Ok(Input::Help),
+        "quit" | "exit" => return Ok(Input::Quit),
+        _ => {}
+    }
+    let point = if input.contains(',') {
+        Point::try_from(input.to_string())
+            .map_err(|_| format!("{} is not a move like 3,4 or D4", input))?
+    } else {
+        match parse_vertex(input, size) {
+            Some(Some(point)) => point,
+            _ => return Err(format!("{} is not a move like 3,4 or D4", input)),
+        }
+    };
+    let in_range = |n: i8| 1 <= n && n as usize <= size;
+    if !in_range(point.row) || !in_range(point.col) {
+        return Err(format!("{} is out of the {}x{} board", input, size, size));
+    }
+    Ok(Input::Move(point))
+}
+
+pub struct HumanPlay {
+    pub game: Game,
+    pub human: Stone,
+    bot: Box<dyn Bot>,
+    // the dead stones the computer marked and accepts
+    proposal: HashSet<Point>,
+}
+
+impl HumanPlay {
+    pub fn new(size: usize, ruleset: Ruleset, human: Stone, bot: Box<dyn Bot>) -> Self {
+        HumanPlay {
+            game: Game::new(size, ruleset),
+            human,
+            bot,
+            proposal: HashSet::new(),
+        }
+    }
+
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        writeln!(output, "you play {:?}. type help for commands.", self.human)?;
+        let mut lines = input.lines();
+        while self.game.status != GameStatus::End {
+            if self.game.status == GameStatus::Continue && self.game.turn != self.human {
+                writeln!(output, "{}", self.play_bot())?;
+                continue;
+            }
+            writeln!(output, "{}", self.game.board)?;
+            if self.game.status == GameStatus::Scoring {
+                writeln!(output, "dead stones: {}", self.dead_stone_list())?;
+            }
+            write!(output, "{:?}> ", self.human)?;
+            output.flush()?;
+            let Some(line) = lines.next() else {
+                return Ok(());
+            };
+            let message = match parse_input(&line?, self.game.board.size()) {
+                Ok(Input::Quit) => return Ok(()),
+                Ok(input) => self.handle(input),
+                Err(err) => Err(err),
+            };
+            match message {
+                Ok(message) => writeln!(output, "{}", message)?,
+                Err(err) => writeln!(output, "{}", err)?,
+            }
+        }
+        writeln!(output, "{}", self.game.board)?;
+        if let Some(result) = self.game.info.result.as_ref() {
+            writeln!(output, "game end: {}", result)?;
+        }
+        Ok(())
+    }
+
+    // the message to show, or the error message
+    pub fn handle(&mut self, input: Input) -> Result<String, String> {
+        let size = self.game.board.size();
+        match input {
+            Input::Move(point) if self.game.status == GameStatus::Scoring => {
+                self.game.toggle_dead(point).map_err(|err| explain(&err))?;
+                let state = if self.game.is_dead(point) {
+                    "dead"
+                } else {
+                    "alive"
+                };
+                Ok(format!(
+                    "the group on {} is marked {}",
+                    describe(point, size),
+                    state
+                ))
+            }
+            Input::Move(point) => {
+                let command = Command::Move {
+                    stone: self.human,
+                    point,
+                };
+                self.game.play(command).map_err(|err| {
+                    format!(
+                        "you cannot play {}: {}",
+                        describe(point, size),
+                        explain(&err)
+                    )
+                })?;
+                Ok(format!("you play {}", describe(point, size)))
+            }
+            Input::Pass => {
+                self.game.play(Command::Pass).map_err(|err| explain(&err))?;
+                Ok(format!("you pass{}", self.propose_dead_stones()))
+            }
+            Input::Done => {
+                self.game.agree(self.human).map_err(|err| explain(&err))?;
+                if self.game.status == GameStatus::Scoring {
+                    // the human changed the marks after the computer accepted them
+                    if self.game.dead_stones() != &self.proposal {
+                        return Err(
+                            "the computer does not agree. mark the dead stones again, or resume"
+                                .to_string(),
+                        );
+                    }
+                    self.game
+                        .agree(self.human.flip())
+                        .map_err(|err| explain(&err))?;
+                }
+                Ok("the dead stones are agreed".to_string())
+            }
+            Input::Resume => {
+                self.game.resume().map_err(|err| explain(&err))?;
+                Ok("the game is resumed".to_string())
+            }
+            Input::Resign => {
+                self.game
+                    .play(Command::Resign)
+                    .map_err(|err| explain(&err))?;
+                Ok("you resign".to_string())
+            }
+            Input::Undo => {
+                if !self
+                    .game
+                    .moves
+                    .iter()
+                    .any(|&(stone, _)| stone == self.human)
+                {
+                    return Err("nothing to undo".to_string());
+                }
+                // take back the replies of the computer too
+                while let Some(&(stone, _)) = self.game.moves.last() {
+                    self.game.undo().map_err(|err| explain(&err))?;
+                    if stone == self.human {
+                        break;
+                    }
+                }
+                Ok("your last move is taken back".to_string())
+            }
+            Input::Hint => match self.hint() {
+                Command::Move { point, .. } => Ok(format!("hint: {}", describe(point, size))),
+                Command::Pass => Ok("hint: pass".to_string()),
+                Command::Resign => Ok("hint: resign".to_string()),
+            },
+            Input::Help => Ok(HELP.to_string()),
+            Input::Quit => Ok(String::new()),
+        }
+    }
+
+    // the command the bot would play for the human
+    pub fn hint(&mut self) -> Command {
+        self.bot.next_command(&self.game)
+    }
+
+    pub fn play_bot(&mut self) -> String {
+        let command = self.bot.next_command(&self.game);
+        if let Err(err) = self.game.play(command) {
+            if self.game.status == GameStatus::End {
+                return format!("{}. you win", err);
+            }
+            self.game.forfeit(self.human.flip(), EndReason::Forfeit);
+            return "the computer played an illegal move. you win".to_string();
+        }
+        match command {
+            Command::Move { point, .. } => {
+                format!("computer plays {}", describe(point, self.game.board.size()))
+            }
+            Command::Pass => format!("computer passes{}", self.propose_dead_stones()),
+            Command::Resign => "computer resigns. you win".to_string(),
+        }
+    }
+
+    // after both players passed, the computer marks the dead stones and accepts them.
+    // the message to add, empty while the game goes on
+    fn propose_dead_stones(&mut self) -> String {
+        if self.game.status != GameStatus::Scoring {
+            return String::new();
+        }
+        for point in self.bot.dead_stones(&self.game) {
+            self.game.set_dead(point, true).ok();
+        }
+        self.proposal = self.game.dead_stones().clone();
+        self.game
+            .agree(self.human.flip())
+            .expect("the game is in the scoring phase");
+        format!(
+            ". the computer marks dead stones: {}. {}",
+            self.dead_stone_list(),
+            SCORING_HELP
+        )
+    }
+
+    // e.g. "C3 D3", or "none"
+    pub fn dead_stone_list(&self) -> String {
+        let size = self.game.board.size();
+        let mut points = self.game.dead_stones().iter().copied().collect::<Vec<_>>();
+        if points.is_empty() {
+            return "none".to_string();
+        }
+        points.sort_by_key(|p| (p.row, p.col));
+        points
+            .iter()
+            .map(|&p| format_vertex(p, size))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+// why the move of the human is not accepted, in plain words
+fn explain(err: &GameError) -> String {
+    match err {
+        GameError::Board(BoardError::OutOfRange(_)) => "it is out of the board".to_string(),
+        GameError::Board(BoardError::Occupied(_)) => "there is already a stone".to_string(),
+        GameError::Board(BoardError::Suicide(_)) => {
+            "the stone would have no liberties without capturing".to_string()
+        }
+        GameError::Board(BoardError::Ko(_)) => {
+            "the ko cannot be taken back at once. play elsewhere first".to_string()
+        }
+        GameError::Board(BoardError::Superko(_)) => {
+            "it repeats a previous position of the game".to_string()
+        }
+        GameError::TimeOver(_) => "you ran out of time".to_string(),
+        GameError::Scoring => format!("both passed. {}", SCORING_HELP),
+        GameError::NoStone(_) => "there is no stone to mark".to_string(),
+        err => err.to_string(),
+    }
+}
+
+// e.g. "D4 (6,4)" on 9x9 board
+fn describe(point: Point, size: usize) -> String {
+    format!(
+        "{} ({},{})",
+        format_vertex(point, size),
+        point.row,
+        point.col
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct PassBot;
+
+    impl Bot for PassBot {
+        fn new() -> Self {
+            PassBot
+        }
+
+        fn next_command(&mut self, _game: &Game) -> Command {
+            Command::Pass
+        }
+    }
+
+    fn run(play: &mut HumanPlay, input: &str) -> String {
+        let mut output = vec![];
+        play.run(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn play_parse_input() {
+        let point = |row, col| Ok(Input::Move(Point { row, col }));
+        assert_eq!(parse_input("3,4", 9), point(3, 4));
+        assert_eq!(parse_input(" 3, 4 ", 9), point(3, 4));
+        assert_eq!(parse_input("D4", 9), point(6, 4));
+        assert_eq!(parse_input("d4", 9), point(6, 4));
+        assert_eq!(parse_input("PASS", 9), Ok(Input::Pass));
+        assert_eq!(parse_input("resign", 9), Ok(Input::Resign));
+        assert_eq!(parse_input("undo", 9), Ok(Input::Undo));
+        assert_eq!(parse_input("hint", 9), Ok(Input::Hint));
+
+        for input in ["", "10,1", "0,3", "300,1", "a,b", "Z1", "D10", "hello"] {
+            assert!(parse_input(input, 9).is_err(), "{}", input);
+        }
+        assert_eq!(
+            parse_input("10,1", 9),
+            Err("10,1 is out of the 9x9 board".to_string())
+        );
+    }
+
+    #[test]
+    fn play_session() {
+        let mut play = HumanPlay::new(9, Ruleset::default(), Stone::Black, Box::new(PassBot));
+        let output = run(&mut play, "3,3\n3,3\nfoo\nundo\nundo\nE5\nhint\n");
+        assert!(output.contains("you play C7 (3,3)"));
+        assert!(output.contains("computer passes"));
+        assert!(output.contains("you cannot play C7 (3,3): there is already a stone"));
+        assert!(output.contains("foo is not a move"));
+        assert!(output.contains("your last move is taken back"));
+        assert!(output.contains("nothing to undo"));
+        assert!(output.contains("hint: pass"));
+        assert_eq!(
+            play.game.moves,
+            vec![
+                (
+                    Stone::Black,
+                    Command::Move {
+                        stone: Stone::Black,
+                        point: Point { row: 5, col: 5 }
+                    }
+                ),
+                (Stone::White, Command::Pass)
+            ]
+        );
+
+        // the game ends by passes of both players and the agreement on the dead stones
+        let output = run(&mut play, "pass\ndone\n");
+        assert!(output.contains("the computer marks dead stones: none"));
+        assert_eq!(play.game.status, GameStatus::End);
+        assert!(output.contains("game end: B+"));
+    }
+
+    #[test]
+    fn play_scoring() {
+        let mut play = HumanPlay::new(9, Ruleset::default(), Stone::Black, Box::new(PassBot));
+        let output = run(&mut play, "E5\npass\npass\nE5\ndone\nresume\n");
+        assert!(output.contains("both passed. mark or unmark dead groups"));
+        assert!(output.contains("the group on E5 (5,5) is marked dead"));
+        assert!(output.contains("dead stones: E5"));
+        // the computer accepted no dead stones
+        assert!(output.contains("the computer does not agree"));
+        assert!(output.contains("the game is resumed"));
+        assert_eq!(play.game.status, GameStatus::Continue);
+        assert!(play.game.dead_stones().is_empty());
+
+        // the computer passes again after the resumption.
+        // the marks are changed back, and the computer accepts them again
+        let output = run(&mut play, "pass\nE5\nE5\ndone\n");
+        assert!(output.contains("marked alive"));
+        assert_eq!(play.game.status, GameStatus::End);
+        assert!(output.contains("game end: B+"));
+    }
+
+    #[test]
+    fn play_resign() {
+        let mut play = HumanPlay::new(9, Ruleset::default(), Stone::White, Box::new(PassBot));
+        let output = run(&mut play, "resign\n");
+        assert!(output.starts_with("you play White."));
+        assert!(output.contains("computer passes"));
+        assert_eq!(play.game.status, GameStatus::End);
+        assert_eq!(play.game.info.result, "B+R".parse().ok());
+    }
+}
@@ -0,0 +1,123 @@
+// rulesets bundle every rule which differs between go rules
+use crate::board::KoRule;
+use crate::score::Scoring;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ruleset {
+    pub ko_rule: KoRule,
+    // whether a move may remove its own group
+    pub suicide_allowed: bool,
+    pub scoring: Scoring,
+    pub komi: f32,
+}
+
+impl Ruleset {
+    pub fn chinese() -> Self {
+        Ruleset {
+            ko_rule: KoRule::PositionalSuperko,
+            suicide_allowed: false,
+            scoring: Scoring::Area,
+            komi: 7.5,
+        }
+    }
+
+    pub fn japanese() -> Self {
+        Ruleset {
+            ko_rule: KoRule::Simple,
+            suicide_allowed: false,
+            scoring: Scoring::Territory,
+            komi: 6.5,
+        }
+    }
+
+    // AGA counts by area, which gives the same result as its territory counting with pass stones
+    pub fn aga() -> Self {
+        Ruleset {
+            ko_rule: KoRule::SituationalSuperko,
+            suicide_allowed: false,
+            scoring: Scoring::Area,
+            komi: 7.5,
+        }
+    }
+
+    pub fn new_zealand() -> Self {
+        Ruleset {
+            ko_rule: KoRule::SituationalSuperko,
+            suicide_allowed: true,
+            scoring: Scoring::Area,
+            komi: 7.0,
+        }
+    }
+
+    // multi stone suicide is legal, and single stone suicide is forbidden by superko
+    // since it repeats the position. the board is scored as it is by area.
+    pub fn tromp_taylor() -> Self {
+        Ruleset {
+            ko_rule: KoRule::PositionalSuperko,
+            suicide_allowed: true,
+            scoring: Scoring::Area,
+            komi: 7.5,
+        }
+    }
+
+    // every preset with its name used by SGF RU property
+    pub fn presets() -> [(&'static str, Ruleset); 5] {
+        [
+            ("Chinese", Self::chinese()),
+            ("Japanese", Self::japanese()),
+            ("AGA", Self::aga()),
+            ("NZ", Self::new_zealand()),
+            ("TrompTaylor", Self::tromp_taylor()),
+        ]
+    }
+
+    pub fn name(&self) -> Option<&'static str> {
+        Self::presets()
+            .into_iter()
+            .find(|(_, ruleset)| {
+                ruleset.ko_rule == self.ko_rule
+                    && ruleset.suicide_allowed == self.suicide_allowed
+                    && ruleset.scoring == self.scoring
+            })
+            .map(|(name, _)| name)
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+            "chinese" | "cn" => Some(Self::chinese()),
+            "japanese" | "jp" => Some(Self::japanese()),
+            "aga" => Some(Self::aga()),
+            "nz" | "newzealand" => Some(Self::new_zealand()),
+            "tromptaylor" | "tt" => Some(Self::tromp_taylor()),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Self::chinese()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ruleset_names() {
+        for (name, ruleset) in Ruleset::presets() {
+            assert_eq!(ruleset.name(), Some(name));
+            assert_eq!(Ruleset::from_name(name), Some(ruleset));
+        }
+        // komi does not change the name
+        let mut ruleset = Ruleset::japanese();
+        ruleset.komi = 0.5;
+        assert_eq!(ruleset.name(), Some("Japanese"));
+        assert_eq!(
+            Ruleset::from_name("New Zealand"),
+            Some(Ruleset::new_zealand())
+        );
+        assert_eq!(Ruleset::from_name("ing"), None);
+    }
+}
@@ -0,0 +1,279 @@
+// counting the score of a finished game
+use crate::board::{Board, BoardCell, Point, Stone};
+use std::collections::HashSet;
+
+// breakdown of the score of one player
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Score {
+    // stones alive on the board, counted only by area scoring
+    pub stones: usize,
+    pub territory: usize,
+    // captured and dead stones of the opponent, counted only by territory scoring
+    pub prisoners: usize,
+    pub komi: f32,
+}
+
+impl Score {
+    pub fn total(&self) -> f32 {
+        (self.stones + self.territory + self.prisoners) as f32 + self.komi
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Scoring {
+    // stones plus territory, a.k.a. chinese rule
+    #[default]
+    Area,
+    // territory plus prisoners, a.k.a. japanese rule
+    Territory,
+}
+
+// owners of the empty points of the board
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Territory {
+    pub black: Vec<Point>,
+    pub white: Vec<Point>,
+    // empty points which reach both colors or no stone
+    pub neutral: Vec<Point>,
+}
+
+// an empty region belongs to a color when it reaches only that color.
+// dead stones are regarded as already removed from the board.
+pub fn find_territory(board: &Board, dead_stones: &HashSet<Point>) -> Territory {
+    let get = |point: Point| match board.get(point) {
+        BoardCell::Space(Some(_)) if dead_stones.contains(&point) => BoardCell::Space(None),
+        cell => cell,
+    };
+    let mut territory = Territory::default();
+    let mut checked_points = HashSet::new();
+    for start_point in board.points() {
+        if checked_points.contains(&start_point) || !get(start_point).is_empty() {
+            continue;
+        }
+        // flood fill the empty region from the start point
+        let mut region = vec![];
+        let mut reaches_black = false;
+        let mut reaches_white = false;
+        let mut check_points = vec![start_point];
+        checked_points.insert(start_point);
+        while let Some(point) = check_points.pop() {
+            region.push(point);
+            for neighbor in point.neighbors() {
+                match get(neighbor) {
+                    BoardCell::Space(None) => {
+                        if checked_points.insert(neighbor) {
+                            check_points.push(neighbor);
+                        }
+                    }
+                    BoardCell::Space(Some(Stone::Black)) => reaches_black = true,
+                    BoardCell::Space(Some(Stone::White)) => reaches_white = true,
+                    BoardCell::Wall => {}
+                }
+            }
+        }
+        match (reaches_black, reaches_white) {
+            (true, false) => territory.black.append(&mut region),
+            (false, true) => territory.white.append(&mut region),
+            _ => territory.neutral.append(&mut region),
+        }
+    }
+    territory
+}
+
+pub fn score(
+    board: &Board,
+    scoring: Scoring,
+    komi: f32,
+    dead_stones: &HashSet<Point>,
+) -> (Score, Score) {
+    match scoring {
+        Scoring::Area => area_score(board, komi, dead_stones),
+        Scoring::Territory => territory_score(board, komi, dead_stones),
+    }
+}
+
+// area scoring, used by chinese rule. stones on the board plus territory.
+pub fn area_score(board: &Board, komi: f32, dead_stones: &HashSet<Point>) -> (Score, Score) {
+    let territory = find_territory(board, dead_stones);
+    let black = Score {
+        stones: count_stones(board, Stone::Black, dead_stones, false),
+        territory: territory.black.len(),
+        komi: 0.0,
+        ..Score::default()
+    };
+    let white = Score {
+        stones: count_stones(board, Stone::White, dead_stones, false),
+        territory: territory.white.len(),
+        komi,
+        ..Score::default()
+    };
+    (black, white)
+}
+
+// territory scoring, used by japanese rule.
+// territory plus stones captured during the game and dead stones removed at the end.
+pub fn territory_score(board: &Board, komi: f32, dead_stones: &HashSet<Point>) -> (Score, Score) {
+    let territory = find_territory(board, dead_stones);
+    let black = Score {
+        territory: territory.black.len(),
+        prisoners: board.black_prisoners + count_stones(board, Stone::White, dead_stones, true),
+        komi: 0.0,
+        ..Score::default()
+    };
+    let white = Score {
+        territory: territory.white.len(),
+        prisoners: board.white_prisoners + count_stones(board, Stone::Black, dead_stones, true),
+        komi,
+        ..Score::default()
+    };
+    (black, white)
+}
+
+fn count_stones(board: &Board, color: Stone, dead_stones: &HashSet<Point>, dead: bool) -> usize {
+    board
+        .points()
+        .filter(|&p| board.get(p).is_same_color(color) && dead_stones.contains(&p) == dead)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn put_stones(board: &mut Board, stone: Stone, points: &[(i8, i8)]) {
+        for &(row, col) in points {
+            board.put(stone, Point { row, col }).unwrap();
+        }
+    }
+
+    #[test]
+    fn area_score_counts_territory() {
+        // ┌─────────────┐
+        // │   ① ② ③ ④ ⑤ │
+        // │ ① ┌─○ ● ┬─┐ │
+        // │ ② ○ ○ ● ┼─┤ │
+        // │ ③ ├─○ ● ┼─┤ │
+        // │ ④ ○ ○ ● ● ● │
+        // │ ⑤ └─○ ● ┴─┘ │
+        let mut board = Board::new(5);
+        put_stones(
+            &mut board,
+            Stone::Black,
+            &[(1, 2), (2, 1), (2, 2), (3, 2), (4, 1), (4, 2), (5, 2)],
+        );
+        put_stones(
+            &mut board,
+            Stone::White,
+            &[(1, 3), (2, 3), (3, 3), (4, 3), (4, 4), (4, 5), (5, 3)],
+        );
+
+        let territory = find_territory(&board, &HashSet::new());
+        let mut black_territory = territory.black.clone();
+        black_territory.sort_by_key(|p| (p.row, p.col));
+        assert_eq!(
+            black_territory,
+            vec![
+                Point { row: 1, col: 1 },
+                Point { row: 3, col: 1 },
+                Point { row: 5, col: 1 },
+            ]
+        );
+        assert_eq!(territory.white.len(), 8);
+        assert!(territory.neutral.is_empty());
+
+        let (black, white) = area_score(&board, 6.5, &HashSet::new());
+        assert_eq!(
+            black,
+            Score {
+                stones: 7,
+                territory: 3,
+                prisoners: 0,
+                komi: 0.0,
+            }
+        );
+        assert_eq!(
+            white,
+            Score {
+                stones: 7,
+                territory: 8,
+                prisoners: 0,
+                komi: 6.5,
+            }
+        );
+        assert_eq!(black.total(), 10.0);
+        assert_eq!(white.total(), 21.5);
+    }
+
+    #[test]
+    fn area_score_neutral_points() {
+        // empty board has no territory
+        let board = Board::new(9);
+        let territory = find_territory(&board, &HashSet::new());
+        assert_eq!(territory.neutral.len(), 81);
+        assert_eq!(area_score(&board, 0.0, &HashSet::new()).0.total(), 0.0);
+
+        // the point between both colors is neutral
+        // ┌─────────┐
+        // │   ① ② ③ │
+        // │ ① ○ ┬─● │
+        // │ ② ○ ┼─● │
+        // │ ③ ○ ┴─● │
+        let mut board = Board::new(3);
+        put_stones(&mut board, Stone::Black, &[(1, 1), (2, 1), (3, 1)]);
+        put_stones(&mut board, Stone::White, &[(1, 3), (2, 3), (3, 3)]);
+        let territory = find_territory(&board, &HashSet::new());
+        assert_eq!(territory.neutral.len(), 3);
+        assert!(territory.black.is_empty());
+        assert!(territory.white.is_empty());
+    }
+
+    #[test]
+    fn territory_score_counts_prisoners_and_dead_stones() {
+        // black captured a white stone at ①-① and the white stone at ③-① is dead.
+        // ┌─────────────┐
+        // │   ① ② ③ ④ ⑤ │
+        // │ ① ┌─○ ○ ● ┐ │
+        // │ ② ○ ┼─○ ● ┤ │
+        // │ ③ ● ┼─○ ● ┤ │
+        // │ ④ ├─┼─○ ● ┤ │
+        // │ ⑤ └─┴─○ ● ┘ │
+        let mut board = Board::new(5);
+        put_stones(&mut board, Stone::White, &[(1, 1)]);
+        put_stones(&mut board, Stone::Black, &[(1, 2), (2, 1)]);
+        assert_eq!(board.black_prisoners, 1);
+        put_stones(
+            &mut board,
+            Stone::Black,
+            &[(1, 3), (2, 3), (3, 3), (4, 3), (5, 3)],
+        );
+        put_stones(
+            &mut board,
+            Stone::White,
+            &[(1, 4), (2, 4), (3, 4), (4, 4), (5, 4), (3, 1)],
+        );
+        let dead_stones = HashSet::from([Point { row: 3, col: 1 }]);
+
+        let (black, white) = territory_score(&board, 6.5, &dead_stones);
+        assert_eq!(
+            black,
+            Score {
+                stones: 0,
+                territory: 8,
+                prisoners: 2,
+                komi: 0.0,
+            }
+        );
+        assert_eq!(black.total(), 10.0);
+        assert_eq!(white.territory, 5);
+        assert_eq!(white.prisoners, 0);
+        assert_eq!(white.total(), 11.5);
+
+        // the same game by area scoring
+        let (black, white) = score(&board, Scoring::Area, 6.5, &dead_stones);
+        assert_eq!(black.stones, 7);
+        assert_eq!(black.territory, 8);
+        assert_eq!(black.total(), 15.0);
+        assert_eq!(white.stones, 5);
+        assert_eq!(white.total(), 16.5);
+    }
+}
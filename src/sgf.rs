@@ -0,0 +1,462 @@
+// SGF (Smart Game Format) FF[4] reader and writer.
+// https://www.red-bean.com/sgf/
+use crate::board::{Board, Point, Stone};
+use crate::game::{Command, Game, GameResult, GameStatus};
+use crate::rules::Ruleset;
+
+// SGF does not specify SZ, the default board is 19x19
+const SGF_DEFAULT_SIZE: usize = 19;
+
+impl Game {
+    pub fn to_sgf(&self) -> String {
+        let mut sgf = String::from("(;FF[4]GM[1]CA[UTF-8]AP[gorust]");
+        sgf += &format!("SZ[{}]KM[{}]", self.board.size(), self.ruleset().komi);
+        if let Some(name) = self.ruleset().name() {
+            sgf += &format!("RU[{}]", name);
+        }
+        if self.handicap > 0 {
+            sgf += &format!("HA[{}]", self.handicap);
+        }
+        if let Some(name) = &self.info.black_player {
+            sgf += &format!("PB[{}]", escape(name));
+        }
+        if let Some(name) = &self.info.white_player {
+            sgf += &format!("PW[{}]", escape(name));
+        }
+        if let Some(result) = &self.info.result {
+            sgf += &format!("RE[{}]", result);
+        }
+        for (property, color) in [("AB", Stone::Black), ("AW", Stone::White)] {
+            let stones = self
+                .setup
+                .iter()
+                .filter(|(stone, _)| *stone == color)
+                .map(|&(_, point)| format!("[{}]", format_point(point)))
+                .collect::<String>();
+            if !stones.is_empty() {
+                sgf += property;
+                sgf += &stones;
+            }
+        }
+        // after setup stones, e.g. handicap, white may move first
+        if !self.setup.is_empty() {
+            let first_player = self.moves.first().map_or(self.turn, |&(stone, _)| stone);
+            sgf += &format!("PL[{}]", format_color(first_player));
+        }
+        for &(stone, command) in self.moves.iter() {
+            let property = format_color(stone);
+            let value = match command {
+                Command::Move { point, .. } => format_point(point),
+                // FF[4] pass is an empty value
+                Command::Pass => String::new(),
+                // resign is recorded only as RE
+                Command::Resign => continue,
+            };
+            sgf += &format!("\n;{}[{}]", property, value);
+        }
+        sgf += ")\n";
+        sgf
+    }
+
+    // read the main line of the first game in the collection.
+    // variations other than the first one are ignored.
+    pub fn from_sgf(text: &str) -> Result<Game, String> {
+        let nodes = Parser::new(text).parse_collection()?;
+        let Some(root) = nodes.first() else {
+            return Err("SGF has no node".to_string());
+        };
+
+        if let Some(game_type) = root.get("GM")
+            && game_type != "1"
+        {
+            return Err(format!("not a game of go: GM[{}]", game_type));
+        }
+        let size = match root.get("SZ") {
+            Some(size) => size
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| format!("unsupported board size: SZ[{}]", size))?,
+            None => SGF_DEFAULT_SIZE,
+        };
+        if !Board::is_valid_size(size) {
+            return Err(format!("unsupported board size: SZ[{}]", size));
+        }
+
+        // unknown rules are played by the default ruleset
+        let mut ruleset = root
+            .get("RU")
+            .and_then(Ruleset::from_name)
+            .unwrap_or_default();
+        if let Some(komi) = root.get("KM") {
+            ruleset.komi = komi
+                .trim()
+                .parse::<f32>()
+                .map_err(|_| format!("invalid komi: KM[{}]", komi))?;
+        }
+        let mut game = Game::new(size, ruleset);
+        if let Some(handicap) = root.get("HA") {
+            game.handicap = handicap
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| format!("invalid handicap: HA[{}]", handicap))?;
+        }
+        // white moves first after handicap stones unless PL tells
+        if game.handicap > 0 {
+            game.turn = Stone::White;
+        }
+        game.info.black_player = root.get("PB").map(str::to_string);
+        game.info.white_player = root.get("PW").map(str::to_string);
+
+        for node in nodes.iter() {
+            for (property, color) in [("AB", Stone::Black), ("AW", Stone::White)] {
+                for value in node.get_all(property) {
+                    for point in parse_point_list(value, size)? {
+                        game.add_setup_stone(color, point).map_err(|err| {
+                            format!("invalid setup stone {}[{}]: {}", property, value, err)
+                        })?;
+                    }
+                }
+            }
+            if let Some(player) = node.get("PL") {
+                game.turn = parse_color(player)?;
+            }
+            for (property, color) in [("B", Stone::Black), ("W", Stone::White)] {
+                if let Some(value) = node.get(property) {
+                    let command = match parse_point(value, size)? {
+                        Some(point) => Command::Move {
+                            stone: color,
+                            point,
+                        },
+                        None => Command::Pass,
+                    };
+                    // records may contain consecutive moves of the same color
+                    game.play_as(color, command)
+                        .map_err(|err| format!("illegal move {}[{}]: {}", property, value, err))?;
+                }
+            }
+        }
+        // the recorded result wins over counting. unknown ones like "Void" are ignored
+        if let Some(result) = root.get("RE").and_then(|re| re.parse::<GameResult>().ok()) {
+            game.status = GameStatus::End;
+            game.info.result = Some(result);
+        }
+        Ok(game)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    properties: Vec<(String, Vec<String>)>,
+}
+
+impl Node {
+    fn get(&self, id: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(property, _)| property == id)
+            .and_then(|(_, values)| values.first())
+            .map(String::as_str)
+    }
+
+    fn get_all<'a>(&'a self, id: &'a str) -> impl Iterator<Item = &'a str> {
+        self.properties
+            .iter()
+            .filter(move |(property, _)| property == id)
+            .flat_map(|(_, values)| values.iter().map(String::as_str))
+    }
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        Parser {
+            chars: text.chars().peekable(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected '{}' but found '{}'", expected, c)),
+            None => Err(format!("expected '{}' but reached the end", expected)),
+        }
+    }
+
+    fn parse_collection(&mut self) -> Result<Vec<Node>, String> {
+        let mut nodes = vec![];
+        self.parse_game_tree(&mut nodes)?;
+        Ok(nodes)
+    }
+
+    // GameTree = "(" Sequence { GameTree } ")"
+    fn parse_game_tree(&mut self, nodes: &mut Vec<Node>) -> Result<(), String> {
+        self.expect('(')?;
+        self.skip_whitespace();
+        while self.chars.next_if_eq(&';').is_some() {
+            nodes.push(self.parse_node()?);
+            self.skip_whitespace();
+        }
+        // the first variation continues the main line
+        let mut is_main_line = true;
+        while self.chars.peek() == Some(&'(') {
+            if is_main_line {
+                self.parse_game_tree(nodes)?;
+                is_main_line = false;
+            } else {
+                self.parse_game_tree(&mut vec![])?;
+            }
+            self.skip_whitespace();
+        }
+        self.expect(')')
+    }
+
+    fn parse_node(&mut self) -> Result<Node, String> {
+        let mut node = Node::default();
+        loop {
+            self.skip_whitespace();
+            let mut id = String::new();
+            // FF[3] allows lower case letters in identifiers, only upper case ones matter
+            while let Some(c) = self.chars.next_if(|c| c.is_ascii_alphabetic()) {
+                if c.is_ascii_uppercase() {
+                    id.push(c);
+                }
+            }
+            if id.is_empty() {
+                return Ok(node);
+            }
+            let mut values = vec![];
+            self.skip_whitespace();
+            while self.chars.peek() == Some(&'[') {
+                values.push(self.parse_value()?);
+                self.skip_whitespace();
+            }
+            if values.is_empty() {
+                return Err(format!("property {} has no value", id));
+            }
+            node.properties.push((id, values));
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<String, String> {
+        self.expect('[')?;
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some(']') => return Ok(value),
+                Some('\\') => match self.chars.next() {
+                    // soft line break
+                    Some('\n') => {}
+                    Some(c) => value.push(c),
+                    None => return Err("unterminated property value".to_string()),
+                },
+                Some(c) => value.push(c),
+                None => return Err("unterminated property value".to_string()),
+            }
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(']', "\\]")
+}
+
+fn format_point(point: Point) -> String {
+    let letter = |n: i8| (b'a' + n as u8 - 1) as char;
+    format!("{}{}", letter(point.col), letter(point.row))
+}
+
+// None means pass
+fn parse_point(value: &str, size: usize) -> Result<Option<Point>, String> {
+    let value = value.trim();
+    // FF[3] pass is "tt"
+    if value.is_empty() || (value == "tt" && size <= 19) {
+        return Ok(None);
+    }
+    let coordinates = value
+        .chars()
+        .map(|c| match c {
+            'a'..='z' => Some(c as u8 - b'a' + 1),
+            'A'..='Z' => Some(c as u8 - b'A' + 27),
+            _ => None,
+        })
+        .collect::<Option<Vec<u8>>>();
+    match coordinates.as_deref() {
+        Some(&[col, row]) if col as usize <= size && row as usize <= size => Ok(Some(Point {
+            row: row as i8,
+            col: col as i8,
+        })),
+        _ => Err(format!("invalid point: [{}]", value)),
+    }
+}
+
+// compressed point lists like "aa:cc" mean the rectangle between both corners
+fn parse_point_list(value: &str, size: usize) -> Result<Vec<Point>, String> {
+    let corners = value
+        .split(':')
+        .map(|v| parse_point(v, size)?.ok_or(format!("invalid point: [{}]", value)))
+        .collect::<Result<Vec<Point>, String>>()?;
+    match corners[..] {
+        [point] => Ok(vec![point]),
+        [from, to] => Ok((from.row.min(to.row)..=from.row.max(to.row))
+            .flat_map(|row| {
+                (from.col.min(to.col)..=from.col.max(to.col)).map(move |col| Point { row, col })
+            })
+            .collect()),
+        _ => Err(format!("invalid point list: [{}]", value)),
+    }
+}
+
+fn format_color(stone: Stone) -> &'static str {
+    match stone {
+        Stone::Black => "B",
+        Stone::White => "W",
+    }
+}
+
+fn parse_color(value: &str) -> Result<Stone, String> {
+    match value.trim() {
+        "B" | "b" => Ok(Stone::Black),
+        "W" | "w" => Ok(Stone::White),
+        _ => Err(format!("invalid color: [{}]", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::EndReason;
+
+    #[test]
+    fn sgf_round_trip() {
+        let mut game = Game::new(9, Ruleset::japanese());
+        game.info.black_player = Some("black [bot]".to_string());
+        game.info.white_player = Some("white\\bot".to_string());
+        game.add_setup_stone(Stone::Black, Point { row: 3, col: 3 })
+            .unwrap();
+        game.add_setup_stone(Stone::White, Point { row: 7, col: 7 })
+            .unwrap();
+        game.play(Command::Move {
+            stone: Stone::Black,
+            point: Point { row: 1, col: 2 },
+        })
+        .unwrap();
+        game.play(Command::Pass).unwrap();
+        game.play(Command::Move {
+            stone: Stone::Black,
+            point: Point { row: 9, col: 8 },
+        })
+        .unwrap();
+        game.play(Command::Pass).unwrap();
+        game.play(Command::Pass).unwrap();
+        game.agree(Stone::Black).unwrap();
+        game.agree(Stone::White).unwrap();
+        assert_eq!(game.info.result, Some(game.game_end()));
+
+        let sgf = game.to_sgf();
+        assert!(sgf.starts_with("(;FF[4]GM[1]"));
+        assert!(sgf.contains("SZ[9]KM[6.5]RU[Japanese]"));
+        assert!(sgf.contains("PB[black [bot\\]]"));
+        assert!(sgf.contains("AB[cc]AW[gg]"));
+        assert!(sgf.contains(";B[ba]\n;W[]\n;B[hi]"));
+
+        let loaded = Game::from_sgf(&sgf).unwrap();
+        assert_eq!(loaded.board, game.board);
+        assert_eq!(loaded.ruleset(), game.ruleset());
+        // RE keeps only the margin of the result, not the breakdown
+        assert_eq!(
+            loaded.info.result.map(|result| result.to_string()),
+            game.info.result.map(|result| result.to_string())
+        );
+        assert_eq!(
+            loaded.info.result.unwrap().reason,
+            EndReason::Margin(game.game_end().margin().unwrap())
+        );
+        let mut info = loaded.info.clone();
+        info.result = game.info.result;
+        assert_eq!(info, game.info);
+        assert_eq!(loaded.setup, game.setup);
+        assert_eq!(loaded.moves, game.moves);
+        assert_eq!(loaded.turn, game.turn);
+        assert_eq!(loaded.status, GameStatus::End);
+    }
+
+    #[test]
+    fn sgf_read_record() {
+        // a record written by another program with
+        // variations, compressed setup, FF[3] pass and escaped text.
+        let sgf = r"(;GM[1]FF[4]SZ[5]KM[0.5]PB[Honinbo \] Shusaku]PW[Gennan]
+            RE[W+2.5]C[comment
+            with line break]AB[aa:ab]
+            ;W[ba];B[ca]
+            (;W[tt];B[bb])
+            (;W[ee]))";
+        let game = Game::from_sgf(sgf).unwrap();
+        assert_eq!(game.board.size(), 5);
+        assert_eq!(game.ruleset().komi, 0.5);
+        assert_eq!(game.info.black_player.as_deref(), Some("Honinbo ] Shusaku"));
+        assert_eq!(game.info.result, "W+2.5".parse().ok());
+        assert_eq!(game.status, GameStatus::End);
+        assert_eq!(
+            game.setup,
+            vec![
+                (Stone::Black, Point { row: 1, col: 1 }),
+                (Stone::Black, Point { row: 2, col: 1 }),
+            ]
+        );
+        assert_eq!(game.moves.len(), 4);
+        assert_eq!(game.moves[2], (Stone::White, Command::Pass));
+        // white stone at ba is captured by bb
+        assert!(game.board.get(Point { row: 1, col: 2 }).is_empty());
+        assert!(game.board.get(Point { row: 5, col: 5 }).is_empty());
+        assert_eq!(game.board.black_prisoners, 1);
+        assert_eq!(game.turn, Stone::White);
+    }
+
+    #[test]
+    fn sgf_handicap() {
+        let mut game = Game::new(9, Ruleset::japanese());
+        game.set_fixed_handicap(3).unwrap();
+        let sgf = game.to_sgf();
+        assert!(sgf.contains("KM[0.5]RU[Japanese]HA[3]AB[cg][gc][cc]PL[W]"));
+
+        let loaded = Game::from_sgf(&sgf).unwrap();
+        assert_eq!(loaded.handicap, 3);
+        assert_eq!(loaded.setup, game.setup);
+        assert_eq!(loaded.turn, Stone::White);
+        assert_eq!(loaded.ruleset().komi, 0.5);
+
+        // records without PL
+        let game = Game::from_sgf("(;SZ[9]HA[2]AB[cg][gc];W[ee])").unwrap();
+        assert_eq!(
+            game.moves,
+            vec![(
+                Stone::White,
+                Command::Move {
+                    stone: Stone::White,
+                    point: Point { row: 5, col: 5 }
+                }
+            )]
+        );
+        assert_eq!(game.turn, Stone::Black);
+    }
+
+    #[test]
+    fn sgf_read_errors() {
+        assert!(Game::from_sgf("").is_err());
+        assert!(Game::from_sgf("(;GM[2])").is_err());
+        assert!(Game::from_sgf("(;SZ[40])").is_err());
+        assert!(Game::from_sgf("(;SZ[9];B[zz])").is_err());
+        assert!(Game::from_sgf("(;SZ[9];B[aa];W[aa])").is_err());
+        assert!(Game::from_sgf("(;SZ[9];B[aa]").is_err());
+        // default size is 19
+        assert_eq!(Game::from_sgf("(;)").unwrap().board.size(), 19);
+    }
+}
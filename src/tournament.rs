@@ -0,0 +1,337 @@
+// matches of many games between two bots
+use crate::board::Stone;
+use crate::bot::{Bot, settle_dead_stones};
+use crate::clock::{Clock, TimeControl, TimeSource, WallTime};
+use crate::game::{EndReason, Game, GameResult, GameStatus};
+use crate::rules::Ruleset;
+use std::fmt;
+use std::sync::Arc;
+
+// z value of the 95% confidence interval
+const Z_95: f32 = 1.96;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+// a game of the match from the view of the first bot
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchGame {
+    pub color: Stone,
+    pub outcome: Outcome,
+    // score of the first bot minus score of the second bot. None unless decided by counting
+    pub margin: Option<f32>,
+    // number of commands including passes
+    pub length: usize,
+    pub result: GameResult,
+}
+
+pub struct Match {
+    pub size: usize,
+    pub ruleset: Ruleset,
+    // games are scored as they are after this many commands, in case bots never pass
+    pub max_moves: usize,
+    // a bot loses when its time is over. no limit when None
+    pub time_control: Option<TimeControl>,
+    // the clocks read the time from here, the wall clock unless replaced
+    pub time_source: Arc<dyn TimeSource + Send + Sync>,
+}
+
+impl Match {
+    pub fn new(size: usize, ruleset: Ruleset) -> Self {
+        Match {
+            size,
+            ruleset,
+            max_moves: size * size * 3,
+            time_control: None,
+            time_source: Arc::new(WallTime::new()),
+        }
+    }
+
+    pub fn play(&self, games: usize, first: &mut dyn Bot, second: &mut dyn Bot) -> MatchStats {
+        let games = (0..games)
+            .map(|round| self.play_game(round, first, second))
+            .collect();
+        MatchStats { games }
+    }
+
+    // the first bot plays black in even rounds and white in odd rounds.
+    // a bot loses by forfeit when it plays an illegal command, or runs out of time.
+    pub fn play_game(&self, round: usize, first: &mut dyn Bot, second: &mut dyn Bot) -> MatchGame {
+        let color = if round.is_multiple_of(2) {
+            Stone::Black
+        } else {
+            Stone::White
+        };
+        let mut game = Game::new(self.size, self.ruleset);
+        game.clock = self
+            .time_control
+            .map(|control| Clock::new(control, self.time_source.clone()));
+        while game.status == GameStatus::Continue && game.moves.len() < self.max_moves {
+            let command = if game.turn == color {
+                first.next_command(&game)
+            } else {
+                second.next_command(&game)
+            };
+            // a move of the other color is illegal too.
+            // the game is over already when the time is over
+            if game.play(command).is_err() && game.status == GameStatus::Continue {
+                game.forfeit(game.turn, EndReason::Forfeit);
+            }
+        }
+        settle_dead_stones(&mut game, first, second);
+
+        let result = match game.info.result {
+            Some(result) => result,
+            None => game.game_end(),
+        };
+        let outcome = match result.winner_stone() {
+            Some(winner) if winner == color => Outcome::Win,
+            Some(_) => Outcome::Loss,
+            None => Outcome::Draw,
+        };
+        // margin is of the winner
+        let margin = result.margin().map(|margin| {
+            if outcome == Outcome::Loss {
+                -margin
+            } else {
+                margin
+            }
+        });
+        MatchGame {
+            color,
+            outcome,
+            margin,
+            length: game.moves.len(),
+            result,
+        }
+    }
+}
+
+// statistics from the view of the first bot
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MatchStats {
+    pub games: Vec<MatchGame>,
+}
+
+impl MatchStats {
+    pub fn wins(&self) -> usize {
+        self.count(Outcome::Win)
+    }
+
+    pub fn losses(&self) -> usize {
+        self.count(Outcome::Loss)
+    }
+
+    pub fn draws(&self) -> usize {
+        self.count(Outcome::Draw)
+    }
+
+    fn count(&self, outcome: Outcome) -> usize {
+        self.games.iter().filter(|g| g.outcome == outcome).count()
+    }
+
+    pub fn forfeits(&self) -> usize {
+        self.games
+            .iter()
+            .filter(|g| matches!(g.result.reason, EndReason::Forfeit | EndReason::Time))
+            .count()
+    }
+
+    // a draw counts as half a win
+    pub fn win_rate(&self) -> f32 {
+        if self.games.is_empty() {
+            return 0.0;
+        }
+        (self.wins() as f32 + self.draws() as f32 / 2.0) / self.games.len() as f32
+    }
+
+    // 95% confidence interval of the win rate by normal approximation
+    pub fn confidence_interval(&self) -> (f32, f32) {
+        if self.games.is_empty() {
+            return (0.0, 1.0);
+        }
+        let win_rate = self.win_rate();
+        let error = Z_95 * (win_rate * (1.0 - win_rate) / self.games.len() as f32).sqrt();
+        ((win_rate - error).max(0.0), (win_rate + error).min(1.0))
+    }
+
+    // average over the games decided by score
+    pub fn average_margin(&self) -> Option<f32> {
+        let margins = self
+            .games
+            .iter()
+            .filter_map(|g| g.margin)
+            .collect::<Vec<_>>();
+        if margins.is_empty() {
+            return None;
+        }
+        Some(margins.iter().sum::<f32>() / margins.len() as f32)
+    }
+
+    pub fn average_length(&self) -> f32 {
+        if self.games.is_empty() {
+            return 0.0;
+        }
+        self.games.iter().map(|g| g.length).sum::<usize>() as f32 / self.games.len() as f32
+    }
+}
+
+impl fmt::Display for MatchStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (lower, upper) = self.confidence_interval();
+        writeln!(f, "games: {}", self.games.len())?;
+        writeln!(
+            f,
+            "wins: {}, losses: {}, draws: {}, forfeits: {}",
+            self.wins(),
+            self.losses(),
+            self.draws(),
+            self.forfeits()
+        )?;
+        writeln!(
+            f,
+            "win rate: {:.1}% (95% CI: {:.1}% - {:.1}%)",
+            self.win_rate() * 100.0,
+            lower * 100.0,
+            upper * 100.0
+        )?;
+        match self.average_margin() {
+            Some(margin) => writeln!(f, "average margin: {:+.1}", margin)?,
+            None => writeln!(f, "average margin: -")?,
+        }
+        write!(f, "average length: {:.1} moves", self.average_length())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Point;
+    use crate::bot::RandomBot;
+    use crate::clock::MockTime;
+    use crate::game::Command;
+    use std::time::Duration;
+
+    struct PassBot;
+
+    impl Bot for PassBot {
+        fn new() -> Self {
+            PassBot
+        }
+
+        fn next_command(&mut self, _game: &Game) -> Command {
+            Command::Pass
+        }
+    }
+
+    // plays on the same point forever
+    struct CornerBot;
+
+    impl Bot for CornerBot {
+        fn new() -> Self {
+            CornerBot
+        }
+
+        fn next_command(&mut self, game: &Game) -> Command {
+            Command::Move {
+                stone: game.turn,
+                point: Point { row: 1, col: 1 },
+            }
+        }
+    }
+
+    #[test]
+    fn match_illegal_move_forfeits() {
+        let stats =
+            Match::new(5, Ruleset::default()).play(4, &mut RandomBot::new(), &mut CornerBot::new());
+        assert_eq!(stats.wins(), 4);
+        assert_eq!(stats.forfeits(), 4);
+        assert_eq!(stats.average_margin(), None);
+        let colors = stats.games.iter().map(|g| g.color).collect::<Vec<_>>();
+        assert_eq!(
+            colors,
+            vec![Stone::Black, Stone::White, Stone::Black, Stone::White]
+        );
+        assert_eq!(stats.games[0].result.to_string(), "B+F");
+        assert_eq!(stats.games[1].result.to_string(), "W+F");
+    }
+
+    // thinks longer than the time control of the test
+    struct SlowBot {
+        time: Arc<MockTime>,
+    }
+
+    impl Bot for SlowBot {
+        fn new() -> Self {
+            SlowBot {
+                time: Arc::new(MockTime::default()),
+            }
+        }
+
+        fn next_command(&mut self, _game: &Game) -> Command {
+            self.time.advance(Duration::from_secs(20));
+            Command::Pass
+        }
+    }
+
+    #[test]
+    fn match_time_forfeit() {
+        let mut slow_bot = SlowBot::new();
+        let mut tournament = Match::new(5, Ruleset::default());
+        tournament.time_control = Some(TimeControl::Absolute {
+            main: Duration::from_secs(10),
+        });
+        tournament.time_source = slow_bot.time.clone();
+        let stats = tournament.play(2, &mut PassBot::new(), &mut slow_bot);
+        assert_eq!(stats.wins(), 2);
+        assert_eq!(stats.forfeits(), 2);
+        assert_eq!(stats.games[0].result.to_string(), "B+T");
+        assert_eq!(stats.games[1].result.to_string(), "W+T");
+    }
+
+    #[test]
+    fn match_scores_games() {
+        let mut ruleset = Ruleset {
+            komi: 0.0,
+            ..Ruleset::default()
+        };
+        let stats = Match::new(5, ruleset).play(2, &mut PassBot::new(), &mut PassBot::new());
+        assert_eq!(stats.draws(), 2);
+        assert_eq!(stats.average_margin(), Some(0.0));
+        assert_eq!(stats.average_length(), 2.0);
+        assert_eq!(stats.win_rate(), 0.5);
+
+        // white wins by komi
+        ruleset.komi = 7.5;
+        let stats = Match::new(5, ruleset).play(2, &mut PassBot::new(), &mut PassBot::new());
+        let outcomes = stats.games.iter().map(|g| g.outcome).collect::<Vec<_>>();
+        assert_eq!(outcomes, vec![Outcome::Loss, Outcome::Win]);
+        assert_eq!(stats.games[0].margin, Some(-7.5));
+        assert_eq!(stats.games[1].margin, Some(7.5));
+    }
+
+    #[test]
+    fn match_stats_confidence_interval() {
+        let game = |outcome| MatchGame {
+            color: Stone::Black,
+            outcome,
+            margin: Some(1.0),
+            length: 10,
+            result: "B+1".parse().unwrap(),
+        };
+        let mut stats = MatchStats::default();
+        assert_eq!(stats.confidence_interval(), (0.0, 1.0));
+
+        stats.games = [vec![game(Outcome::Win); 64], vec![game(Outcome::Loss); 36]].concat();
+        let (lower, upper) = stats.confidence_interval();
+        assert!((lower - 0.546).abs() < 0.001);
+        assert!((upper - 0.734).abs() < 0.001);
+
+        stats.games = vec![game(Outcome::Win); 10];
+        assert_eq!(stats.confidence_interval(), (1.0, 1.0));
+    }
+}
@@ -0,0 +1,337 @@
+// full-screen terminal UI drawing the board in place, played by a cursor
+use crate::board::{Point, Stone};
+use crate::game::{Command, GameStatus};
+use crate::gtp::format_vertex;
+use crate::play::{HumanPlay, Input};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::{PrintStyledContent, Stylize};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, execute, queue, style::Print};
+use std::io::{self, Write};
+
+const KEYS: &str = "arrows: move  enter/space: put  p: pass  u: undo  h: hint  r: resign  q: quit";
+const SCORING_KEYS: &str =
+    "arrows: move  enter/space: mark dead  d: done  c: resume  u: undo  q: quit";
+// lines of the move list in the side panel
+const MOVE_LIST_LENGTH: usize = 10;
+
+pub struct Tui {
+    pub play: HumanPlay,
+    pub cursor: Point,
+    pub message: String,
+}
+
+// restores the terminal even when drawing fails
+struct RawScreen;
+
+impl RawScreen {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, cursor::Hide)?;
+        Ok(RawScreen)
+    }
+}
+
+impl Drop for RawScreen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), LeaveAlternateScreen, cursor::Show);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+impl Tui {
+    pub fn new(play: HumanPlay) -> Self {
+        let center = (play.game.board.size() as i8 + 1) / 2;
+        Tui {
+            play,
+            cursor: Point {
+                row: center,
+                col: center,
+            },
+            message: String::new(),
+        }
+    }
+
+    pub fn run(&mut self) -> io::Result<()> {
+        let _screen = RawScreen::enter()?;
+        let mut output = io::stdout();
+        loop {
+            let game = &self.play.game;
+            if game.status == GameStatus::Continue && game.turn != self.play.human {
+                self.message = "computer is thinking...".to_string();
+                self.draw(&mut output)?;
+                self.message = self.play.play_bot();
+                continue;
+            }
+            self.draw(&mut output)?;
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && !self.handle_key(key.code)
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    // false to quit
+    pub fn handle_key(&mut self, key: KeyCode) -> bool {
+        let size = self.play.game.board.size() as i8;
+        let input = match key {
+            KeyCode::Up => {
+                self.cursor.row = (self.cursor.row - 1).max(1);
+                return true;
+            }
+            KeyCode::Down => {
+                self.cursor.row = (self.cursor.row + 1).min(size);
+                return true;
+            }
+            KeyCode::Left => {
+                self.cursor.col = (self.cursor.col - 1).max(1);
+                return true;
+            }
+            KeyCode::Right => {
+                self.cursor.col = (self.cursor.col + 1).min(size);
+                return true;
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            // the game is kept on the screen after the end
+            _ if self.play.game.status == GameStatus::End => return true,
+            KeyCode::Enter | KeyCode::Char(' ') => Input::Move(self.cursor),
+            KeyCode::Char('p') => Input::Pass,
+            KeyCode::Char('u') => Input::Undo,
+            KeyCode::Char('r') => Input::Resign,
+            KeyCode::Char('d') => Input::Done,
+            KeyCode::Char('c') => Input::Resume,
+            KeyCode::Char('h') => {
+                self.message = match self.play.hint() {
+                    Command::Move { point, .. } => {
+                        self.cursor = point;
+                        "hint: the cursor is on the move".to_string()
+                    }
+                    Command::Pass => "hint: pass".to_string(),
+                    Command::Resign => "hint: resign".to_string(),
+                };
+                return true;
+            }
+            _ => return true,
+        };
+        self.message = match self.play.handle(input) {
+            Ok(message) | Err(message) => message,
+        };
+        true
+    }
+
+    fn draw(&mut self, output: &mut impl Write) -> io::Result<()> {
+        let board = &self.play.game.board;
+        let star_points = board.star_points();
+        let board_text = board.to_string();
+        queue!(output, Clear(ClearType::All))?;
+        for (y, line) in board_text.lines().enumerate() {
+            queue!(output, cursor::MoveTo(0, y as u16), Print(line))?;
+        }
+        // draw the highlighted cells over the board
+        let last_move = self.last_move();
+        let ko_point = self.play.game.board.ko_point();
+        let board = &self.play.game.board;
+        for point in board.points() {
+            let text = board.cell_text(point, &star_points);
+            let styled = if point == self.cursor {
+                text.reverse()
+            } else if Some(point) == last_move {
+                text.red().bold()
+            } else if Some(point) == ko_point {
+                text.on_yellow()
+            } else if self.play.game.is_dead(point) {
+                text.dark_grey().crossed_out()
+            } else {
+                continue;
+            };
+            // "│ ① " is on the left of the first column
+            let x = 4 + 2 * (point.col as u16 - 1);
+            let y = 1 + point.row as u16;
+            queue!(output, cursor::MoveTo(x, y), PrintStyledContent(styled))?;
+        }
+
+        let panel_x = board_text.lines().next().map_or(0, |l| l.chars().count()) as u16 + 2;
+        for (y, line) in self.panel_lines().iter().enumerate() {
+            queue!(output, cursor::MoveTo(panel_x, y as u16), Print(line))?;
+        }
+        let bottom = board_text.lines().count() as u16;
+        let keys = match self.play.game.status {
+            GameStatus::Scoring => SCORING_KEYS,
+            _ => KEYS,
+        };
+        queue!(
+            output,
+            cursor::MoveTo(0, bottom + 1),
+            Print(&self.message),
+            cursor::MoveTo(0, bottom + 2),
+            Print(keys)
+        )?;
+        output.flush()
+    }
+
+    fn last_move(&self) -> Option<Point> {
+        match self.play.game.moves.last() {
+            Some(&(_, Command::Move { point, .. })) => Some(point),
+            _ => None,
+        }
+    }
+
+    // captures, turn or scoring, ko point, score estimate and the recent moves
+    pub fn panel_lines(&self) -> Vec<String> {
+        let game = &self.play.game;
+        let size = game.board.size();
+        let player = |stone: Stone| {
+            if stone == self.play.human {
+                "you"
+            } else {
+                "computer"
+            }
+        };
+        let mut lines = vec![
+            format!(
+                "Black ({}) captures: {}",
+                player(Stone::Black),
+                game.board.black_prisoners
+            ),
+            format!(
+                "White ({}) captures: {}",
+                player(Stone::White),
+                game.board.white_prisoners
+            ),
+        ];
+        lines.push(match (&game.info.result, &game.status) {
+            (Some(result), GameStatus::End) => format!("result: {}", result),
+            (_, GameStatus::Scoring) => {
+                format!("scoring: {} dead stones", game.dead_stones().len())
+            }
+            _ => format!("to move: {:?}", game.turn),
+        });
+        let ko_point = game.board.ko_point();
+        lines.push(format!(
+            "ko: {}",
+            ko_point.map_or("-".to_string(), |p| format_vertex(p, size))
+        ));
+        lines.push(format!("estimate: {}", game.game_end()));
+        lines.push("moves:".to_string());
+        let first = game.moves.len().saturating_sub(MOVE_LIST_LENGTH);
+        for (i, &(stone, command)) in game.moves.iter().enumerate().skip(first) {
+            let color = match stone {
+                Stone::Black => "B",
+                Stone::White => "W",
+            };
+            let vertex = match command {
+                Command::Move { point, .. } => format_vertex(point, size),
+                Command::Pass => "pass".to_string(),
+                Command::Resign => "resign".to_string(),
+            };
+            lines.push(format!("{:>4}. {} {}", i + 1, color, vertex));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::Bot;
+    use crate::game::Game;
+    use crate::rules::Ruleset;
+
+    // plays on the first empty point from the top left
+    struct FirstBot;
+
+    impl Bot for FirstBot {
+        fn new() -> Self {
+            FirstBot
+        }
+
+        fn next_command(&mut self, game: &Game) -> Command {
+            match game.board.points().find(|&p| game.board.get(p).is_empty()) {
+                Some(point) => Command::Move {
+                    stone: game.turn,
+                    point,
+                },
+                None => Command::Pass,
+            }
+        }
+    }
+
+    fn tui() -> Tui {
+        Tui::new(HumanPlay::new(
+            5,
+            Ruleset::default(),
+            Stone::Black,
+            Box::new(FirstBot),
+        ))
+    }
+
+    #[test]
+    fn tui_cursor() {
+        let mut tui = tui();
+        assert_eq!(tui.cursor, Point { row: 3, col: 3 });
+        for _ in 0..5 {
+            tui.handle_key(KeyCode::Up);
+            tui.handle_key(KeyCode::Right);
+        }
+        assert_eq!(tui.cursor, Point { row: 1, col: 5 });
+        tui.handle_key(KeyCode::Down);
+        tui.handle_key(KeyCode::Left);
+        assert_eq!(tui.cursor, Point { row: 2, col: 4 });
+
+        // the hint moves the cursor
+        tui.handle_key(KeyCode::Char('h'));
+        assert_eq!(tui.cursor, Point { row: 1, col: 1 });
+        assert!(!tui.handle_key(KeyCode::Char('q')));
+    }
+
+    #[test]
+    fn tui_put_and_panel() {
+        let mut tui = tui();
+        assert!(tui.handle_key(KeyCode::Enter));
+        assert_eq!(tui.message, "you play C3 (3,3)");
+        tui.message = tui.play.play_bot();
+        assert_eq!(tui.message, "computer plays A5 (1,1)");
+        tui.handle_key(KeyCode::Char(' '));
+        assert!(tui.message.starts_with("you cannot play C3 (3,3)"));
+
+        let panel = tui.panel_lines();
+        assert_eq!(panel[0], "Black (you) captures: 0");
+        assert_eq!(panel[1], "White (computer) captures: 0");
+        assert_eq!(panel[2], "to move: Black");
+        assert_eq!(panel[3], "ko: -");
+        assert!(panel[4].starts_with("estimate: "));
+        assert_eq!(&panel[5..], ["moves:", "   1. B C3", "   2. W A5"]);
+
+        tui.handle_key(KeyCode::Char('r'));
+        assert_eq!(tui.panel_lines()[2], "result: W+R");
+        assert_eq!(tui.panel_lines().last().unwrap(), "   3. B resign");
+        // no more moves after the end
+        tui.handle_key(KeyCode::Char('u'));
+        assert_eq!(tui.play.game.moves.len(), 3);
+    }
+
+    #[test]
+    fn tui_scoring() {
+        let mut tui = tui();
+        let game = &mut tui.play.game;
+        game.play(Command::Move {
+            stone: Stone::Black,
+            point: Point { row: 3, col: 3 },
+        })
+        .unwrap();
+        game.play(Command::Pass).unwrap();
+        game.play(Command::Pass).unwrap();
+
+        // the cursor marks the group under it
+        tui.handle_key(KeyCode::Enter);
+        assert_eq!(tui.message, "the group on C3 (3,3) is marked dead");
+        assert_eq!(tui.panel_lines()[2], "scoring: 1 dead stones");
+        tui.handle_key(KeyCode::Char('c'));
+        assert_eq!(tui.play.game.status, GameStatus::Continue);
+        assert_eq!(tui.panel_lines()[2], "to move: White");
+        tui.handle_key(KeyCode::Char('d'));
+        assert!(tui.play.game.dead_stones().is_empty());
+    }
+}